@@ -0,0 +1,182 @@
+// This crate shows compiler errors (and warnings) the way rustc does, like this:
+//
+//     error: invalid character '@'
+//      --> <input>:1:3
+//       |
+//     1 | (a@b)
+//       |   ^
+//       = help: identifiers can only have letters, digits, and underscores
+//
+// It doesn't know anything about tokens or ASTs. All it needs is the source code and a *byte
+// range* that says which part of the source to point at, so any of the lexer/parser examples
+// can use it.
+
+use std::fmt::{ Display, Formatter, Result as FmtResult };
+use std::ops::Range;
+
+use colored::{ Color, Colorize };
+use unicode_segmentation::UnicodeSegmentation;
+
+// ------------------------------------------------------------------------------------------------
+// Level
+// ------------------------------------------------------------------------------------------------
+
+// How bad the problem is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+	Error,
+	Warning,
+	Note,
+}
+
+impl Level {
+	fn color(&self) -> Color {
+		match self {
+			Level::Error   => return Color::BrightRed,
+			Level::Warning => return Color::BrightYellow,
+			Level::Note    => return Color::BrightCyan,
+		}
+	}
+}
+
+impl Display for Level {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			Level::Error   => write!(f, "error"),
+			Level::Warning => write!(f, "warning"),
+			Level::Note    => write!(f, "note"),
+		}
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Diagnostic
+// ------------------------------------------------------------------------------------------------
+
+// "Diagnostic" is the fancy compiler word for "a message about something wrong with the code."
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+	pub level:   Level,
+	pub message: String,
+	pub range:   Range<usize>, // the *byte* range of the source code this message is about.
+	pub notes:   Vec<String>,  // extra information shown after the source line.
+	pub help:    Vec<String>,  // suggestions for how to fix it.
+}
+
+impl Diagnostic {
+	pub fn new(level: Level, message: &str, range: Range<usize>) -> Self {
+		return Diagnostic { level, message: message.into(), range, notes: vec![], help: vec![] };
+	}
+
+	pub fn error(message: &str, range: Range<usize>) -> Self {
+		return Diagnostic::new(Level::Error, message, range);
+	}
+
+	pub fn warning(message: &str, range: Range<usize>) -> Self {
+		return Diagnostic::new(Level::Warning, message, range);
+	}
+
+	// These two take "mut self" and return Self, so you can chain them like:
+	//     Diagnostic::error("oops", 3 .. 4).with_note("...").with_help("...")
+	pub fn with_note(mut self, note: &str) -> Self {
+		self.notes.push(note.into());
+		return self;
+	}
+
+	pub fn with_help(mut self, help: &str) -> Self {
+		self.help.push(help.into());
+		return self;
+	}
+
+	// Turns this diagnostic into text that can be printed. file_name is only used for the
+	// "--> file:line:col" line, so it can be anything, like "<input>" for the REPL.
+	pub fn render(&self, file_name: &str, source: &str) -> String {
+		let color = self.level.color();
+
+		// make sure the range is actually inside the source, so slicing can't panic.
+		let start = clamp_to_char_boundary(source, self.range.start);
+		let end   = clamp_to_char_boundary(source, self.range.end.max(start));
+
+		// find the line that the range starts on.
+		let line_start = source[.. start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+		let line_end   = source[start ..].find('\n').map(|i| i + start).unwrap_or(source.len());
+		let line_num   = source[.. start].matches('\n').count() + 1;
+		let line_text  = &source[line_start .. line_end];
+
+		// columns count graphemes, just like tokens::SourceMap does, so an accented letter is one
+		// column even if it's made of two codepoints. if the range starts in the middle of a
+		// grapheme, we point at the whole grapheme.
+		let start = line_start + grapheme_start(line_text, start - line_start);
+		let col   = source[line_start .. start].graphemes(true).count() + 1;
+
+		// if the range goes onto later lines, we only underline the part on this line.
+		let underline_end = end.min(line_end);
+
+		// the underline has to line up with the line of code above it. tabs are the tricky part,
+		// so we turn them into spaces in both the line and the underline.
+		let padding = display_width(&source[line_start .. start]);
+		let carets  = display_width(&source[start .. underline_end]).max(1);
+
+		// the gutter is the space on the left where the line number goes.
+		let gutter = " ".repeat(line_num.to_string().len());
+		let bar    = "|".bright_blue().bold();
+
+		let mut ret = String::new();
+
+		ret += &format!("{}{} {}\n",
+			self.level.to_string().color(color).bold(), ":".bold(), self.message.bold());
+		ret += &format!("{}{} {}:{}:{}\n", gutter, "-->".bright_blue().bold(), file_name, line_num, col);
+		ret += &format!("{} {}\n", gutter, bar);
+		ret += &format!("{} {} {}\n",
+			line_num.to_string().bright_blue().bold(), bar, line_text.replace('\t', TAB));
+		ret += &format!("{} {} {}{}\n",
+			gutter, bar, " ".repeat(padding), "^".repeat(carets).color(color).bold());
+
+		for note in &self.notes {
+			ret += &format!("{} {} {}: {}\n", gutter, "=".bright_blue().bold(), "note".bold(), note);
+		}
+
+		for help in &self.help {
+			ret += &format!("{} {} {}: {}\n", gutter, "=".bright_blue().bold(), "help".bold(), help);
+		}
+
+		return ret;
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Helpers
+// ------------------------------------------------------------------------------------------------
+
+// what tabs are replaced with when displaying a line of code.
+const TAB: &str = "    ";
+
+// How many columns a piece of text takes up on the screen, with tabs as 4 spaces. Each grapheme
+// is one column, so combining marks (like the accent in a decomposed "é") take up no space of
+// their own.
+fn display_width(s: &str) -> usize {
+	return s.graphemes(true).map(|g| if g == "\t" { TAB.len() } else { 1 }).sum();
+}
+
+// The byte offset of the start of the grapheme that offset is in. (the end of the line counts as
+// a place to start, too, since a range can point just past the last character.)
+fn grapheme_start(line: &str, offset: usize) -> usize {
+	return line.grapheme_indices(true)
+		.map(|(i, _)| i)
+		.chain(std::iter::once(line.len()))
+		.take_while(|&i| i <= offset)
+		.last()
+		.unwrap_or(0);
+}
+
+// Byte offsets that are past the end, or in the middle of a multi-byte character, would make
+// slicing panic. This moves them back to a place that's safe to slice at.
+fn clamp_to_char_boundary(source: &str, offset: usize) -> usize {
+	let mut offset = offset.min(source.len());
+
+	while !source.is_char_boundary(offset) {
+		offset -= 1;
+	}
+
+	return offset;
+}
@@ -0,0 +1,198 @@
+// The builtin functions: the ones written in Rust, that are in the global environment when the
+// interpreter starts.
+//
+//     add, sub, mul          integer math. add and mul take any number of arguments, and sub
+//                            takes at least one: (sub 10 2 3) is 5, and (sub 5) is -5.
+//     div, mod               integer division and remainder. (div 7 2) is 3, and (mod 7 2) is 1.
+//     lt, le, gt, ge         comparing two ints: <, <=, >, and >=.
+//     eq, ne                 whether two values are equal (any kind of values, not just ints).
+//     not                    true if its argument is false, and false if it's true.
+//     cons, car, cdr         make a pair, and get the first and second half of it.
+//     list                   makes a list out of its arguments.
+//     null                   true if its argument is the empty list.
+
+use std::rc::Rc;
+
+use crate::*;
+
+// A builtin function's Rust code gets the arguments (there are always the right number of them),
+// and gives back the value, or what went wrong.
+pub type BuiltinFn = fn(&[Value]) -> Result<Value, ErrorKind>;
+
+pub struct Builtin {
+	pub name:  &'static str,
+	pub arity: Arity,
+	pub func:  BuiltinFn,
+}
+
+// This is a static array, so each Builtin is made once, and Value::Builtin can just point to one.
+pub static BUILTINS: &[Builtin] = &[
+	Builtin { name: "add",  arity: Arity::AtLeast(0), func: add  },
+	Builtin { name: "sub",  arity: Arity::AtLeast(1), func: sub  },
+	Builtin { name: "mul",  arity: Arity::AtLeast(0), func: mul  },
+	Builtin { name: "div",  arity: Arity::Exactly(2), func: div  },
+	Builtin { name: "mod",  arity: Arity::Exactly(2), func: rem  },
+	Builtin { name: "lt",   arity: Arity::Exactly(2), func: lt   },
+	Builtin { name: "le",   arity: Arity::Exactly(2), func: le   },
+	Builtin { name: "gt",   arity: Arity::Exactly(2), func: gt   },
+	Builtin { name: "ge",   arity: Arity::Exactly(2), func: ge   },
+	Builtin { name: "eq",   arity: Arity::Exactly(2), func: eq   },
+	Builtin { name: "ne",   arity: Arity::Exactly(2), func: ne   },
+	Builtin { name: "not",  arity: Arity::Exactly(1), func: not  },
+	Builtin { name: "cons", arity: Arity::Exactly(2), func: cons },
+	Builtin { name: "car",  arity: Arity::Exactly(1), func: car  },
+	Builtin { name: "cdr",  arity: Arity::Exactly(1), func: cdr  },
+	Builtin { name: "list", arity: Arity::AtLeast(0), func: list },
+	Builtin { name: "null", arity: Arity::Exactly(1), func: null },
+];
+
+// ------------------------------------------------------------------------------------------------
+// Math
+// ------------------------------------------------------------------------------------------------
+
+// The checked_ methods return None if the math overflows (or divides by zero), instead of
+// crashing (in debug mode) or silently wrapping around (in release mode).
+
+fn add(args: &[Value]) -> Result<Value, ErrorKind> {
+	return fold("add", 0, args, i64::checked_add);
+}
+
+fn sub(args: &[Value]) -> Result<Value, ErrorKind> {
+	let first = int_arg("sub", &args[0])?;
+
+	// with only one argument, it's negation.
+	if args.len() == 1 {
+		return first.checked_neg().map(Value::Int).ok_or(ErrorKind::Overflow);
+	}
+
+	return fold("sub", first, &args[1 ..], i64::checked_sub);
+}
+
+fn mul(args: &[Value]) -> Result<Value, ErrorKind> {
+	return fold("mul", 1, args, i64::checked_mul);
+}
+
+fn div(args: &[Value]) -> Result<Value, ErrorKind> {
+	return divide("div", args, i64::checked_div);
+}
+
+// (it's not called mod, because mod is a keyword in Rust.)
+fn rem(args: &[Value]) -> Result<Value, ErrorKind> {
+	return divide("mod", args, i64::checked_rem);
+}
+
+// One of the checked_ methods, like i64::checked_add.
+type CheckedOp = fn(i64, i64) -> Option<i64>;
+
+// Applies op to start and each argument from left to right, so (sub 10 2 3) is (10 - 2) - 3.
+fn fold(name: &str, start: i64, args: &[Value], op: CheckedOp) -> Result<Value, ErrorKind> {
+	let mut ret = start;
+
+	for arg in args {
+		ret = op(ret, int_arg(name, arg)?).ok_or(ErrorKind::Overflow)?;
+	}
+
+	return Ok(Value::Int(ret));
+}
+
+// div and mod can fail two ways, so this figures out which one it was.
+fn divide(name: &str, args: &[Value], op: CheckedOp) -> Result<Value, ErrorKind> {
+	let a = int_arg(name, &args[0])?;
+	let b = int_arg(name, &args[1])?;
+
+	if b == 0 {
+		return Err(ErrorKind::DivideByZero);
+	}
+
+	// the only other way for it to fail is i64::MIN / -1, which is one more than i64::MAX.
+	return op(a, b).map(Value::Int).ok_or(ErrorKind::Overflow);
+}
+
+// ------------------------------------------------------------------------------------------------
+// Comparisons
+// ------------------------------------------------------------------------------------------------
+
+fn lt(args: &[Value]) -> Result<Value, ErrorKind> {
+	return compare("lt", args, |a, b| a < b);
+}
+
+fn le(args: &[Value]) -> Result<Value, ErrorKind> {
+	return compare("le", args, |a, b| a <= b);
+}
+
+fn gt(args: &[Value]) -> Result<Value, ErrorKind> {
+	return compare("gt", args, |a, b| a > b);
+}
+
+fn ge(args: &[Value]) -> Result<Value, ErrorKind> {
+	return compare("ge", args, |a, b| a >= b);
+}
+
+// (the closures above don't capture anything, so they can be plain fn pointers.)
+fn compare(name: &str, args: &[Value], op: fn(i64, i64) -> bool) -> Result<Value, ErrorKind> {
+	let a = int_arg(name, &args[0])?;
+	let b = int_arg(name, &args[1])?;
+	return Ok(Value::Bool(op(a, b)));
+}
+
+fn eq(args: &[Value]) -> Result<Value, ErrorKind> {
+	return Ok(Value::Bool(args[0].equals(&args[1])));
+}
+
+fn ne(args: &[Value]) -> Result<Value, ErrorKind> {
+	return Ok(Value::Bool(!args[0].equals(&args[1])));
+}
+
+fn not(args: &[Value]) -> Result<Value, ErrorKind> {
+	match &args[0] {
+		Value::Bool(b) => return Ok(Value::Bool(!b)),
+		other          => return Err(ErrorKind::TypeMismatch("not".into(), "a bool", other.clone())),
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Lists
+// ------------------------------------------------------------------------------------------------
+
+fn cons(args: &[Value]) -> Result<Value, ErrorKind> {
+	return Ok(Value::Pair(Rc::new(args[0].clone()), Rc::new(args[1].clone())));
+}
+
+fn car(args: &[Value]) -> Result<Value, ErrorKind> {
+	let (first, _) = pair_arg("car", &args[0])?;
+	return Ok(first);
+}
+
+fn cdr(args: &[Value]) -> Result<Value, ErrorKind> {
+	let (_, rest) = pair_arg("cdr", &args[0])?;
+	return Ok(rest);
+}
+
+fn list(args: &[Value]) -> Result<Value, ErrorKind> {
+	return Ok(Value::list(args.to_vec()));
+}
+
+fn null(args: &[Value]) -> Result<Value, ErrorKind> {
+	return Ok(Value::Bool(matches!(args[0], Value::Nil)));
+}
+
+// ------------------------------------------------------------------------------------------------
+// Helpers
+// ------------------------------------------------------------------------------------------------
+
+// These check that an argument is the right kind of value, and give a TypeMismatch if it isn't.
+// name is the builtin's name, for the error message.
+
+fn int_arg(name: &str, arg: &Value) -> Result<i64, ErrorKind> {
+	match arg {
+		Value::Int(i) => return Ok(*i),
+		other         => return Err(ErrorKind::TypeMismatch(name.into(), "an int", other.clone())),
+	}
+}
+
+fn pair_arg(name: &str, arg: &Value) -> Result<(Value, Value), ErrorKind> {
+	match arg {
+		Value::Pair(first, rest) => return Ok((first.as_ref().clone(), rest.as_ref().clone())),
+		other => return Err(ErrorKind::TypeMismatch(name.into(), "a pair", other.clone())),
+	}
+}
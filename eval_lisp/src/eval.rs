@@ -0,0 +1,355 @@
+// The evaluator: the code that actually runs an Exp.
+//
+// Most lists are function calls: (f a b) evaluates f, a, and b, and then calls f with the values
+// of a and b. But a few lists are *special forms*, which don't evaluate all their parts first:
+//
+//     (quote EXP)                          EXP as data, not evaluated. 'EXP is short for this.
+//     (define NAME EXP)                    makes a variable in the current scope.
+//     (define (NAME PARAMS...) BODY...)    makes a function. short for (define NAME (lambda ...)).
+//     (lambda (PARAMS...) BODY...)         makes a function.
+//     (if COND THEN ELSE)                  evaluates only one of THEN or ELSE.
+//     (let ((NAME EXP)...) BODY...)        makes some variables, just for BODY.
+//     (begin EXP...)                       evaluates each EXP in order.
+//
+// A BODY is one or more expressions. They're evaluated in order, and the last one's value is the
+// value of the whole thing.
+//
+// (if can't be a function, because a function's arguments are all evaluated before it's called.
+// then (if (eq n 0) 1 (fact (sub n 1))) would call fact forever!)
+
+use std::rc::Rc;
+
+use parsing_lisp::Exp;
+
+use crate::*;
+
+// ------------------------------------------------------------------------------------------------
+// Interpreter
+// ------------------------------------------------------------------------------------------------
+
+// The Interpreter holds the global environment, so that things defined by one call to eval() are
+// still there in the next. That's how the REPL remembers your functions.
+pub struct Interpreter {
+	global: EnvRef,
+
+	// how many evaluations are going on inside of each other right now.
+	depth: usize,
+}
+
+// Each Lisp function call is a few Rust function calls (eval_in(), eval_list(), call()...), and
+// Rust only has so much stack space. If a Lisp program recurses too deeply, Rust's stack would
+// overflow, and that crashes the whole program! So instead, we stop at this many nested
+// evaluations and give a RecursionTooDeep error.
+//
+// A debug build's stack frames are *much* bigger (about 4KB per nested evaluation, vs. a few
+// hundred bytes in release), so it gets a smaller limit. Both leave plenty of room in the main
+// thread's 8MB stack. ((f x) is about 3 nested evaluations, so these are roughly 300 and 3000
+// nested calls of a recursive function.)
+pub const MAX_DEPTH: usize = if cfg!(debug_assertions) { 1_000 } else { 10_000 };
+
+impl Default for Interpreter {
+	fn default() -> Self {
+		return Interpreter::new();
+	}
+}
+
+impl Interpreter {
+	// Makes an interpreter whose global environment has only the builtins in it.
+	pub fn new() -> Self {
+		let global = Env::new(None);
+
+		{
+			let mut env = global.borrow_mut();
+
+			for b in BUILTINS {
+				env.define(b.name, Value::Builtin(b));
+			}
+
+			env.define("true",  Value::Bool(true));
+			env.define("false", Value::Bool(false));
+		}
+
+		return Interpreter { global, depth: 0 };
+	}
+
+	// Evaluates one expression in the global environment.
+	pub fn eval(&mut self, exp: &Exp) -> Result<Value, RuntimeError> {
+		let global = self.global.clone();
+		return self.eval_in(exp, &global).map_err(|e| e.or_in(exp));
+	}
+
+	// Evaluates a whole program (like from Parser::parse()) and gives the last expression's
+	// value. An empty program's value is ().
+	pub fn run(&mut self, program: &[Box<Exp>]) -> Result<Value, RuntimeError> {
+		let mut ret = Value::Nil;
+
+		for exp in program {
+			ret = self.eval(exp)?;
+		}
+
+		return Ok(ret);
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Evaluating
+// ------------------------------------------------------------------------------------------------
+
+impl Interpreter {
+	// Every expression that's evaluated goes through here. It keeps track of how deeply nested
+	// the evaluation is, and stops before Rust runs out of stack (see MAX_DEPTH).
+	fn eval_in(&mut self, exp: &Exp, env: &EnvRef) -> Result<Value, RuntimeError> {
+		if self.depth >= MAX_DEPTH {
+			return Err(ErrorKind::RecursionTooDeep.into());
+		}
+
+		self.depth += 1;
+		let ret = self.eval_exp(exp, env);
+		self.depth -= 1;
+		return ret;
+	}
+
+	fn eval_exp(&mut self, exp: &Exp, env: &EnvRef) -> Result<Value, RuntimeError> {
+		match exp {
+			// numbers and strings evaluate to themselves.
+			Exp::Num(i) => return Ok(Value::Int(*i)),
+			Exp::Str(s) => return Ok(Value::Str(s.clone())),
+
+			// a name evaluates to the value of the variable.
+			Exp::Id(name) => match env.borrow().get(name) {
+				Some(value) => return Ok(value),
+				None        => return Err(ErrorKind::Unbound(name.clone()).into()),
+			},
+
+			// the error says which list it was in, unless something inside of it already did.
+			Exp::Parens(exps) => return self.eval_list(exps, env).map_err(|e| e.or_in(exp)),
+
+			Exp::Dotted(..) =>
+				return Err(RuntimeError::new(ErrorKind::BadForm("(FUNC ARGS...), without a '.'")).or_in(exp)),
+
+			Exp::Error => return Err(ErrorKind::ParseError.into()),
+		}
+	}
+
+	fn eval_list(&mut self, exps: &[Box<Exp>], env: &EnvRef) -> Result<Value, RuntimeError> {
+		// () evaluates to itself, like in Common Lisp. (in Scheme, you have to write '().)
+		let (first, args) = match exps.split_first() {
+			Some(split) => split,
+			None        => return Ok(Value::Nil),
+		};
+
+		// the special forms all start with a name.
+		if let Exp::Id(name) = first.as_ref() {
+			match name.as_str() {
+				"quote"  => return eval_quote(args),
+				"define" => return self.eval_define(args, env),
+				"lambda" => return eval_lambda(args, env),
+				"if"     => return self.eval_if(args, env),
+				"let"    => return self.eval_let(args, env),
+				"begin"  => return self.eval_body(args, env, "(begin EXP...)"),
+				_        => {}
+			}
+		}
+
+		// everything else is a function call. evaluate the function and the arguments, then call it.
+		// (this is a plain loop instead of .map().collect(), because in a debug build, every
+		// iterator method is another stack frame between here and the recursive eval_in().)
+		let func = self.eval_in(first, env)?;
+		let mut arg_values = Vec::with_capacity(args.len());
+
+		for arg in args {
+			arg_values.push(self.eval_in(arg, env)?);
+		}
+
+		return self.call(&func, arg_values);
+	}
+
+	// Calls a function with some arguments that have already been evaluated.
+	fn call(&mut self, func: &Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+		match func {
+			Value::Builtin(b) => {
+				if !b.arity.allows(args.len()) {
+					return Err(ErrorKind::WrongArity(b.name.into(), b.arity, args.len()).into());
+				}
+
+				return (b.func)(&args).map_err(RuntimeError::new);
+			}
+
+			Value::Lambda(l) => {
+				if l.params.len() != args.len() {
+					let name = l.name.clone().unwrap_or_else(|| "<lambda>".into());
+					return Err(ErrorKind::WrongArity(name, Arity::Exactly(l.params.len()), args.len()).into());
+				}
+
+				// the new scope's parent is where the lambda was *made*, not where it's being called.
+				// that's what lets it see the variables it closed over.
+				let env = Env::new(Some(l.env.clone()));
+
+				for (param, arg) in l.params.iter().zip(args) {
+					env.borrow_mut().define(param, arg);
+				}
+
+				return self.eval_body(&l.body, &env, "(lambda (PARAMS...) BODY...)");
+			}
+
+			other => return Err(ErrorKind::NotAFunction(other.clone()).into()),
+		}
+	}
+
+	// --- special forms ---
+
+	// The args here are everything after the special form's name.
+
+	// (define NAME EXP) or (define (NAME PARAMS...) BODY...)
+	// The value of a define is the name, as a symbol, so the REPL shows what was defined.
+	fn eval_define(&mut self, args: &[Box<Exp>], env: &EnvRef) -> Result<Value, RuntimeError> {
+		const SHAPE: &str = "(define NAME EXP) or (define (NAME PARAMS...) BODY...)";
+
+		let (name, value) = match args {
+			[name, exp] if matches!(name.as_ref(), Exp::Id(_)) => {
+				let name = id_name(name).unwrap();
+				(name, self.eval_in(exp, env)?)
+			}
+
+			// the function shorthand. (define (f x) BODY...) is (define f (lambda (x) BODY...)),
+			// except that the function knows its name.
+			[header, body @ ..] if !body.is_empty() => match header.as_ref() {
+				Exp::Parens(parts) if !parts.is_empty() => {
+					let name   = id_name(&parts[0]).ok_or(ErrorKind::BadForm(SHAPE))?;
+					let params = parse_params(&parts[1 ..], SHAPE)?;
+					(name, make_lambda(Some(name), params, body, env))
+				}
+
+				_ => return Err(ErrorKind::BadForm(SHAPE).into()),
+			},
+
+			_ => return Err(ErrorKind::BadForm(SHAPE).into()),
+		};
+
+		env.borrow_mut().define(name, value);
+		return Ok(Value::Symbol(name.into()));
+	}
+
+	// (if COND THEN ELSE)
+	fn eval_if(&mut self, args: &[Box<Exp>], env: &EnvRef) -> Result<Value, RuntimeError> {
+		let (cond, then, els) = match args {
+			[cond, then, els] => (cond, then, els),
+			_                 => return Err(ErrorKind::BadForm("(if COND THEN ELSE)").into()),
+		};
+
+		// there's no "truthiness" here: the condition has to be a bool. (lots of languages let you use
+		// 0 or "" as false, and lots of bugs come from that!)
+		match self.eval_in(cond, env)? {
+			Value::Bool(true)  => return self.eval_in(then, env),
+			Value::Bool(false) => return self.eval_in(els, env),
+			other              => return Err(ErrorKind::TypeMismatch("if".into(), "a bool", other).into()),
+		}
+	}
+
+	// (let ((NAME EXP)...) BODY...)
+	fn eval_let(&mut self, args: &[Box<Exp>], env: &EnvRef) -> Result<Value, RuntimeError> {
+		const SHAPE: &str = "(let ((NAME EXP)...) BODY...)";
+
+		let (bindings, body) = match args {
+			[bindings, body @ ..] => match bindings.as_ref() {
+				Exp::Parens(bindings) => (bindings, body),
+				_                     => return Err(ErrorKind::BadForm(SHAPE).into()),
+			},
+
+			_ => return Err(ErrorKind::BadForm(SHAPE).into()),
+		};
+
+		// the values are evaluated in the *outer* scope, so (let ((x 1) (y x)) ...) can't see the new
+		// x when it's making y. that's how Scheme's let works too.
+		let inner = Env::new(Some(env.clone()));
+
+		for binding in bindings {
+			let (name, exp) = match binding.as_ref() {
+				Exp::Parens(parts) if parts.len() == 2 => (id_name(&parts[0]), &parts[1]),
+				_                                      => (None, binding),
+			};
+
+			let name  = name.ok_or(ErrorKind::BadForm(SHAPE))?;
+			let value = self.eval_in(exp, env)?;
+			inner.borrow_mut().define(name, value);
+		}
+
+		return self.eval_body(body, &inner, SHAPE);
+	}
+
+	// BODY: EXP+, the last one is the value.
+	fn eval_body(&mut self, body: &[Box<Exp>], env: &EnvRef, shape: &'static str) -> Result<Value, RuntimeError> {
+		let (last, rest) = match body.split_last() {
+			Some(split) => split,
+			None        => return Err(ErrorKind::BadForm(shape).into()),
+		};
+
+		for exp in rest {
+			self.eval_in(exp, env)?;
+		}
+
+		return self.eval_in(last, env);
+	}
+}
+
+// (quote EXP)
+fn eval_quote(args: &[Box<Exp>]) -> Result<Value, RuntimeError> {
+	match args {
+		[exp] => return Ok(Value::from_exp(exp)?),
+		_     => return Err(ErrorKind::BadForm("(quote EXP)").into()),
+	}
+}
+
+// (lambda (PARAMS...) BODY...)
+fn eval_lambda(args: &[Box<Exp>], env: &EnvRef) -> Result<Value, RuntimeError> {
+	const SHAPE: &str = "(lambda (PARAMS...) BODY...)";
+
+	match args {
+		[params, body @ ..] if !body.is_empty() => match params.as_ref() {
+			Exp::Parens(params) => {
+				let params = parse_params(params, SHAPE)?;
+				return Ok(make_lambda(None, params, body, env));
+			}
+
+			_ => return Err(ErrorKind::BadForm(SHAPE).into()),
+		},
+
+		_ => return Err(ErrorKind::BadForm(SHAPE).into()),
+	}
+}
+
+// The body has to have at least one expression in it.
+fn make_lambda(name: Option<&str>, params: Vec<String>, body: &[Box<Exp>], env: &EnvRef) -> Value {
+	return Value::Lambda(Rc::new(Lambda {
+		name:   name.map(String::from),
+		params,
+		body:   body.into(),
+		env:    env.clone(),
+	}));
+}
+
+// ------------------------------------------------------------------------------------------------
+// Helpers
+// ------------------------------------------------------------------------------------------------
+
+// The name, if exp is an Id.
+fn id_name(exp: &Exp) -> Option<&str> {
+	match exp {
+		Exp::Id(name) => return Some(name),
+		_             => return None,
+	}
+}
+
+// A lambda's parameter list has to be all names.
+fn parse_params(params: &[Box<Exp>], shape: &'static str) -> Result<Vec<String>, RuntimeError> {
+	let mut ret = vec![];
+
+	for p in params {
+		match id_name(p) {
+			Some(name) => ret.push(name.to_string()),
+			None       => return Err(ErrorKind::BadForm(shape).into()),
+		}
+	}
+
+	return Ok(ret);
+}
@@ -0,0 +1,387 @@
+// A tree-walking interpreter for the Lisp that parsing_lisp parses. "Tree-walking" means it runs
+// the program by walking over the AST (the tree of Exps) directly: to evaluate (add 1 (mul 2 3)),
+// it evaluates each piece, then adds them up. No compiling to anything else first!
+//
+// The language is a tiny Scheme. It has:
+//
+// - values: ints, bools (true and false), strings, symbols, lists, and functions.
+// - special forms: quote, define, lambda, if, let, and begin (see eval.rs).
+// - builtin functions: integer math, comparisons, and list functions (see builtins.rs).
+//
+// The lexer only allows normal identifiers as names, so the builtins are called things like add
+// and lt instead of + and <.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{ Debug, Display, Formatter, Result as FmtResult };
+use std::rc::Rc;
+
+use parsing_lisp::Exp;
+
+mod builtins;
+mod eval;
+
+pub use crate::builtins::*;
+pub use crate::eval::*;
+
+// ------------------------------------------------------------------------------------------------
+// Value type
+// ------------------------------------------------------------------------------------------------
+
+// A Value is what an expression evaluates to. Exp is the *code*; Value is the *data* that the
+// code computes.
+#[derive(Clone)]
+pub enum Value {
+	Int(i64),
+	Bool(bool),
+	Str(String),
+	Symbol(String), // what a quoted name evaluates to: 'x is the symbol x.
+
+	// lists are built out of pairs, like in every Lisp. (1 2 3) is really
+	// Pair(1, Pair(2, Pair(3, Nil))). the Rcs let lists share their tails, so cons doesn't have to
+	// copy the whole list.
+	Nil, // the empty list, ().
+	Pair(Rc<Value>, Rc<Value>),
+
+	// functions. Builtins are written in Rust, and Lambdas are written in Lisp.
+	Builtin(&'static Builtin),
+	Lambda(Rc<Lambda>),
+}
+
+impl Value {
+	// Makes a list out of some values.
+	pub fn list(items: Vec<Value>) -> Value {
+		// a list is built from the back to the front, since each pair points to the rest of it.
+		let mut ret = Value::Nil;
+
+		for item in items.into_iter().rev() {
+			ret = Value::Pair(Rc::new(item), Rc::new(ret));
+		}
+
+		return ret;
+	}
+
+	// Turns some code into data. This is what quote does: '(add 1 2) is a list of the symbol
+	// add and the ints 1 and 2, not 3.
+	pub fn from_exp(exp: &Exp) -> Result<Value, ErrorKind> {
+		match exp {
+			Exp::Id(name) => return Ok(Value::Symbol(name.clone())),
+			Exp::Num(i)   => return Ok(Value::Int(*i)),
+			Exp::Str(s)   => return Ok(Value::Str(s.clone())),
+			Exp::Error    => return Err(ErrorKind::ParseError),
+
+			Exp::Parens(exps) => {
+				let items = exps.iter().map(|e| Value::from_exp(e)).collect::<Result<Vec<_>, _>>()?;
+				return Ok(Value::list(items));
+			}
+
+			// (a b . c) is just like (a b), except the end is c instead of ().
+			Exp::Dotted(exps, tail) => {
+				let mut ret = Value::from_exp(tail)?;
+
+				for e in exps.iter().rev() {
+					ret = Value::Pair(Rc::new(Value::from_exp(e)?), Rc::new(ret));
+				}
+
+				return Ok(ret);
+			}
+		}
+	}
+
+	// What kind of value this is, for error messages, like "expected an int, but got a string".
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Value::Int(..)                         => return "an int",
+			Value::Bool(..)                        => return "a bool",
+			Value::Str(..)                         => return "a string",
+			Value::Symbol(..)                      => return "a symbol",
+			Value::Nil                             => return "the empty list",
+			Value::Pair(..)                        => return "a pair",
+			Value::Builtin(..) | Value::Lambda(..) => return "a function",
+		}
+	}
+
+	// Whether two values are the same. Lists are compared item by item, but functions are only
+	// equal to *themselves*. (telling whether two functions do the same thing is impossible in
+	// general. look up "Rice's theorem" if you're curious!)
+	//
+	// Lists are compared with a loop that walks down both of them, and it only recurses into the
+	// *items*. recursing on the rest of the list too would use one Rust stack frame per item, and
+	// a long enough list would overflow the stack.
+	pub fn equals(&self, other: &Value) -> bool {
+		let mut a = self;
+		let mut b = other;
+
+		loop {
+			match (a, b) {
+				(Value::Pair(a1, d1), Value::Pair(a2, d2)) => {
+					if !a1.equals(a2) {
+						return false;
+					}
+
+					a = d1;
+					b = d2;
+				}
+
+				(Value::Int(a),     Value::Int(b))     => return a == b,
+				(Value::Bool(a),    Value::Bool(b))    => return a == b,
+				(Value::Str(a),     Value::Str(b))     => return a == b,
+				(Value::Symbol(a),  Value::Symbol(b))  => return a == b,
+				(Value::Nil,        Value::Nil)        => return true,
+				(Value::Builtin(a), Value::Builtin(b)) => return std::ptr::eq(*a, *b),
+				(Value::Lambda(a),  Value::Lambda(b))  => return Rc::ptr_eq(a, b),
+				_                                      => return false,
+			}
+		}
+	}
+}
+
+// Rust drops a value by dropping everything inside it first. For a list, that means dropping the
+// first pair drops the rest of the list, which drops the rest of *that*... one stack frame per
+// item, and a list with 100,000 items in it overflows the stack! So this drops the rest of a list
+// with a loop instead: it takes the rest out of each pair before that pair is dropped.
+impl Drop for Value {
+	fn drop(&mut self) {
+		let mut rest = match self {
+			Value::Pair(_, rest) => take_rest(rest),
+			_                    => return,
+		};
+
+		// each time around, we own the rest of the list. if nothing else is using it (try_unwrap
+		// succeeds), we take *its* rest before dropping it, so dropping it doesn't recurse. if
+		// something else is still using it, we're done: it'll be dropped by whoever has it.
+		while let Some(next) = rest {
+			rest = match Rc::try_unwrap(next) {
+				Ok(Value::Pair(_, ref mut next_rest)) => take_rest(next_rest),
+				_                                     => None,
+			};
+		}
+	}
+}
+
+// Takes the rest of a list out of a pair, leaving () in its place. Only pairs are taken, since
+// dropping anything else doesn't recurse down a list.
+fn take_rest(rest: &mut Rc<Value>) -> Option<Rc<Value>> {
+	match rest.as_ref() {
+		Value::Pair(..) => return Some(std::mem::replace(rest, Rc::new(Value::Nil))),
+		_               => return None,
+	}
+}
+
+// Values print the way you'd write them in the code (except for functions, which have no way to
+// be written).
+impl Display for Value {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			Value::Int(i)    => write!(f, "{}", i),
+			Value::Bool(b)   => write!(f, "{}", b),
+			Value::Str(s)    => write!(f, "{:?}", s),
+			Value::Symbol(s) => write!(f, "{}", s),
+			Value::Nil       => write!(f, "()"),
+
+			Value::Pair(first, rest) => {
+				write!(f, "({}", first)?;
+
+				// follow the pairs to the end of the list. if it ends in something other than (),
+				// that's a dotted list, and the end is written after a '.'.
+				let mut rest = rest.as_ref();
+
+				loop {
+					match rest {
+						Value::Nil              => break,
+						Value::Pair(item, tail) => { write!(f, " {}", item)?; rest = tail; }
+						other                   => { write!(f, " . {}", other)?; break; }
+					}
+				}
+
+				write!(f, ")")
+			}
+
+			Value::Builtin(b) => write!(f, "<builtin {}>", b.name),
+			Value::Lambda(l)  => match &l.name {
+				Some(name) => write!(f, "<function {}>", name),
+				None       => write!(f, "<lambda>"),
+			},
+		}
+	}
+}
+
+// A #[derive]d Debug would print a lambda's whole environment, which has the lambda in it, which
+// has the environment in it... forever. So Debug just prints it like Display does.
+impl Debug for Value {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "{}", self)
+	}
+}
+
+// A function written in Lisp, made by lambda (or define, which uses lambda).
+pub struct Lambda {
+	name:   Option<String>, // the name it was defined with, if any. only used for printing it.
+	params: Vec<String>,
+	body:   Rc<[Box<Exp>]>,
+
+	// the environment the lambda was made in. this is what makes it a *closure*: when it's called,
+	// it can still see the variables that were around when it was made, even if that function
+	// has returned since then.
+	env: EnvRef,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Environments
+// ------------------------------------------------------------------------------------------------
+
+// An environment holds the variables in one scope. Each one has a parent, which is the scope
+// around it, and looking up a name goes up the parents until it finds it. The global environment
+// (with the builtins in it) is at the top.
+//
+// Environments are shared: every lambda made in a scope points to that scope's environment, and
+// define can change it later. So they're Rc<RefCell<...>>, which is Rust's way of saying "shared,
+// and changeable by whoever has it."
+//
+// (one downside: a recursive function is in an environment that points to the function, which
+// points to the environment... Rc can't free that cycle, so it stays in memory until the program
+// ends. real interpreters use a garbage collector instead.)
+pub type EnvRef = Rc<RefCell<Env>>;
+
+pub struct Env {
+	vars:   HashMap<String, Value>,
+	parent: Option<EnvRef>,
+}
+
+impl Env {
+	pub fn new(parent: Option<EnvRef>) -> EnvRef {
+		return Rc::new(RefCell::new(Env { vars: HashMap::new(), parent }));
+	}
+
+	// Looks up a variable in this scope, then the scopes around it.
+	pub fn get(&self, name: &str) -> Option<Value> {
+		if let Some(value) = self.vars.get(name) {
+			return Some(value.clone());
+		}
+
+		match &self.parent {
+			Some(parent) => return parent.borrow().get(name),
+			None         => return None,
+		}
+	}
+
+	// Makes a variable in this scope (or changes it, if it's already here).
+	pub fn define(&mut self, name: &str, value: Value) {
+		self.vars.insert(name.into(), value);
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// RuntimeError type
+// ------------------------------------------------------------------------------------------------
+
+// How many arguments a function takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+	Exactly(usize),
+	AtLeast(usize),
+}
+
+impl Arity {
+	pub fn allows(&self, n: usize) -> bool {
+		match self {
+			Arity::Exactly(m) => return n == *m,
+			Arity::AtLeast(m) => return n >= *m,
+		}
+	}
+}
+
+impl Display for Arity {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			Arity::Exactly(1) => write!(f, "1 argument"),
+			Arity::Exactly(n) => write!(f, "{} arguments", n),
+			Arity::AtLeast(1) => write!(f, "at least 1 argument"),
+			Arity::AtLeast(n) => write!(f, "at least {} arguments", n),
+		}
+	}
+}
+
+// What went wrong.
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+	Unbound(String),                           // the name.
+	WrongArity(String, Arity, usize),          // the function's name, what it takes, and what it got.
+	TypeMismatch(String, &'static str, Value), // who wanted it, what it wanted, and what it got.
+	NotAFunction(Value),                       // the value that was called.
+	BadForm(&'static str),                     // how the special form is supposed to look.
+	DivideByZero,
+	Overflow,
+	RecursionTooDeep,                          // more than MAX_DEPTH evaluations inside each other.
+	ParseError,                                // there's an Exp::Error in the code.
+}
+
+impl Display for ErrorKind {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		use ErrorKind::*;
+
+		match self {
+			Unbound(name) =>
+				write!(f, "unbound variable '{}'", name),
+			WrongArity(name, arity, got) =>
+				write!(f, "'{}' takes {}, but was given {}", name, arity, got),
+			TypeMismatch(name, expected, got) =>
+				write!(f, "'{}' needs {}, but got {} ({})", name, expected, got, got.type_name()),
+			NotAFunction(value) =>
+				write!(f, "{} is {}, so it can't be called", value, value.type_name()),
+			BadForm(shape) =>
+				write!(f, "this should look like {}", shape),
+			DivideByZero =>
+				write!(f, "division by zero"),
+			Overflow =>
+				write!(f, "integer overflow"),
+			RecursionTooDeep =>
+				write!(f, "recursion too deep (is there a base case it never reaches?)"),
+			ParseError =>
+				write!(f, "can't run code that has parse errors"),
+		}
+	}
+}
+
+// A RuntimeError is an ErrorKind, plus the expression that it happened in. That's the innermost
+// list being evaluated, like (add x 1) for an unbound x, since just "x" wouldn't tell you much.
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+	pub kind: ErrorKind,
+
+	// this is only None while the error is on its way out of the interpreter. by the time
+	// Interpreter::eval() returns it, it's always filled in.
+	pub exp: Option<Exp>,
+}
+
+impl RuntimeError {
+	pub fn new(kind: ErrorKind) -> Self {
+		return RuntimeError { kind, exp: None };
+	}
+
+	// Says which expression the error was in, unless an inner one already did.
+	pub(crate) fn or_in(mut self, exp: &Exp) -> Self {
+		if self.exp.is_none() {
+			self.exp = Some(exp.clone());
+		}
+
+		return self;
+	}
+}
+
+impl Display for RuntimeError {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match &self.exp {
+			Some(exp) => write!(f, "{}, in {}", self.kind, exp),
+			None      => write!(f, "{}", self.kind),
+		}
+	}
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl From<ErrorKind> for RuntimeError {
+	fn from(kind: ErrorKind) -> Self {
+		return RuntimeError::new(kind);
+	}
+}
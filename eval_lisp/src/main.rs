@@ -0,0 +1,72 @@
+
+use eval_lisp::*;
+use parsing_lisp::Parser;
+
+fn main() {
+	// One interpreter runs all of these, so the functions defined in one are still around for
+	// the ones after it.
+	let mut interp = Interpreter::new();
+
+	// Math, and a recursive function.
+	run(&mut interp, "(add 1 (mul 2 3))");
+	run(&mut interp, "(define (fact n) (if (le n 1) 1 (mul n (fact (sub n 1)))))");
+	run(&mut interp, "(fact 20)");
+
+	// Closures: make_adder returns a lambda which remembers n, even after make_adder returns.
+	run(&mut interp, "(define (make_adder n) (lambda (x) (add x n)))");
+	run(&mut interp, "(define add5 (make_adder 5))");
+	run(&mut interp, "(add5 10)");
+
+	// let and begin.
+	run(&mut interp, "(let ((x 3) (y 4)) (add (mul x x) (mul y y)))");
+	run(&mut interp, "(begin (define z 10) (mul z z))");
+
+	// Lists, quote, and dotted pairs.
+	run(&mut interp, "(cons 1 (cons 2 ()))");
+	run(&mut interp, "(list 1 (list 2 3) 'four \"five\")");
+	run(&mut interp, "(cons 1 2) '(a b . c)");
+	run(&mut interp, "(define (sum lst) (if (null lst) 0 (add (car lst) (sum (cdr lst)))))");
+	run(&mut interp, "(sum '(1 2 3 4 5))");
+
+	// Runtime errors. Each one says which expression it happened in.
+	run(&mut interp, "(add 1 (mul y 2))");
+	run(&mut interp, "(fact 1 2)");
+	run(&mut interp, "(add 1 \"two\")");
+	run(&mut interp, "(if 1 2 3)");
+	run(&mut interp, "(car '())");
+	run(&mut interp, "(5 6)");
+	run(&mut interp, "(div 1 0)");
+	run(&mut interp, "(fact 30)");
+	run(&mut interp, "(lambda x x)");
+
+	// Infinite recursion is an error too, instead of crashing the interpreter. Afterwards, it
+	// still works fine.
+	run(&mut interp, "(define (forever n) (add 1 (forever n)))");
+	run(&mut interp, "(forever 1)");
+	run(&mut interp, "(fact 5)");
+}
+
+// Parses the code, then runs each expression in it and prints what it evaluated to.
+fn run(interp: &mut Interpreter, source: &str) {
+	println!("> {}", source);
+
+	let program = match Parser::parse_str(source) {
+		Ok(program) => program,
+		Err(err)    => {
+			println!("{}\n", err);
+			return;
+		}
+	};
+
+	for exp in &program {
+		match interp.eval(exp) {
+			Ok(value) => println!("{}", value),
+			Err(err)  => {
+				println!("runtime error: {}", err);
+				break;
+			}
+		}
+	}
+
+	println!();
+}
@@ -0,0 +1,67 @@
+// Long lists used to overflow the stack, since dropping and comparing them recursed once per item.
+// These make sure they don't anymore. (tests run on threads with a smaller stack than the main
+// thread, so they'd find it even sooner than a real program would.)
+
+use std::rc::Rc;
+
+use eval_lisp::*;
+use parsing_lisp::Parser;
+
+const LEN: usize = 100_000;
+
+fn long_list(len: usize) -> Value {
+	return Value::list((0 .. len as i64).map(Value::Int).collect());
+}
+
+// How many items are in a list. (a loop, for the same reason as above.)
+fn length(list: &Value) -> usize {
+	let mut ret  = 0;
+	let mut list = list;
+
+	while let Value::Pair(_, rest) = list {
+		ret += 1;
+		list = rest;
+	}
+
+	return ret;
+}
+
+#[test]
+fn dropping_a_long_list() {
+	let list = long_list(LEN);
+	drop(list);
+}
+
+#[test]
+fn comparing_long_lists() {
+	let a = long_list(LEN);
+	let b = long_list(LEN);
+	assert!(a.equals(&b));
+
+	// the difference is right at the end, so it has to walk all the way there.
+	let c = Value::list((0 .. LEN as i64).map(|i| Value::Int(if i + 1 == LEN as i64 { -1 } else { i })).collect());
+	assert!(!a.equals(&c));
+}
+
+#[test]
+fn long_lists_in_the_interpreter() {
+	let items  = vec!["1"; LEN].join(" ");
+	let source = format!("(define x '({})) (define y (cdr (cons 0 x))) (eq x y)", items);
+	let program = Parser::parse_str(&source).expect("it parses");
+
+	// x and y are the same list, and then the interpreter (with the list in it) is dropped.
+	let mut interp = Interpreter::new();
+	let value      = interp.run(&program).expect("it runs");
+	assert!(value.equals(&Value::Bool(true)));
+}
+
+#[test]
+fn dropping_a_list_that_shares_its_tail() {
+	// longer is (0 . tail), so it shares all of tail's pairs. dropping it has to stop at the pairs
+	// that tail is still using, and leave them alone.
+	let tail   = long_list(LEN);
+	let longer = Value::Pair(Rc::new(Value::Int(0)), Rc::new(tail.clone()));
+	drop(longer);
+
+	assert_eq!(length(&tail), LEN);
+}
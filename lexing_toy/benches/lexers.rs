@@ -0,0 +1,132 @@
+// This compares how fast the main lexer and the zero-copy lexer are on a big generated input, and
+// how much faster relexing after an edit is than lexing everything again. Run it with
+// "cargo bench". (It's a plain program instead of using a benchmarking library, so
+// the numbers are rough, but the difference is big enough that it doesn't matter.)
+
+use std::hint::black_box;
+use std::time::{ Duration, Instant };
+
+use lexing_toy::{ zero_copy, Edit, Relexer, SourceMap, TokenKind };
+
+// how many edits to time the relexer on.
+const EDITS: usize = 200;
+
+// how many times to lex the input with each lexer. the best time is the one reported, since
+// the slower runs are usually slow because of other things happening on the computer.
+const RUNS: usize = 10;
+
+fn main() {
+	let source = generate_input(20_000);
+	let mb     = source.len() as f64 / (1024.0 * 1024.0);
+
+	println!("input: {:.2} MB, {} lines", mb, source.lines().count());
+
+	// make sure they agree before timing them!
+	check_same(&source);
+
+	let main_time = best_time(|| lexing_toy::lex(black_box(&source)).unwrap().len());
+	let fast_time = best_time(|| zero_copy::lex(black_box(&source)).unwrap().len());
+
+	report("lexing_toy::lex",            mb, main_time);
+	report("lexing_toy::zero_copy::lex", mb, fast_time);
+
+	println!("speedup: {:.2}x", main_time.as_secs_f64() / fast_time.as_secs_f64());
+	println!();
+
+	relex(&source);
+}
+
+// Types a character at a bunch of places spread through the source, and times relexing after
+// each one against lexing the whole new source again.
+fn relex(source: &str) {
+	let mut relexer   = Relexer::new(source);
+	let mut relex_time = Duration::ZERO;
+	let mut full_time  = Duration::ZERO;
+	let len = relexer.source_map().num_chars();
+
+	for i in 0 .. EDITS {
+		let pos  = len * i / EDITS;
+		let edit = Edit::new(pos .. pos, "x");
+
+		let start = Instant::now();
+		black_box(relexer.edit(black_box(&edit)));
+		relex_time += start.elapsed();
+
+		let start = Instant::now();
+		black_box(lexing_toy::lex_recovering(black_box(relexer.source())));
+		full_time += start.elapsed();
+	}
+
+	println!("{} edits:", EDITS);
+	println!("{:28} {:8.2} ms per edit", "Relexer::edit", relex_time.as_secs_f64() * 1000.0 / EDITS as f64);
+	println!("{:28} {:8.2} ms per edit", "lexing_toy::lex_recovering", full_time.as_secs_f64() * 1000.0 / EDITS as f64);
+	println!("speedup: {:.2}x", full_time.as_secs_f64() / relex_time.as_secs_f64());
+}
+
+// Checks that the two lexers give the same tokens, in the same places. The main lexer's spans
+// are codepoint indices and the zero-copy one's are byte ranges, so the SourceMap converts them.
+fn check_same(source: &str) {
+	let map      = SourceMap::new(source);
+	let expected = lexing_toy::lex(source).expect("input should lex");
+	let actual   = zero_copy::lex(source).expect("input should lex");
+
+	for (i, (e, a)) in expected.iter().zip(&actual).enumerate() {
+		assert!(same_kind(&e.kind, &a.kind) && map.byte_range(e.span) == a.span,
+			"token #{} is different: {:?} at {:?} vs. {:?} at {:?}",
+			i, e.kind, map.byte_range(e.span), a.kind, a.span);
+	}
+
+	assert_eq!(expected.len(), actual.len(), "the two lexers gave a different number of tokens");
+}
+
+// The two lexers have their own TokenKinds, so they can't be compared with ==.
+fn same_kind(e: &TokenKind, a: &zero_copy::TokenKind) -> bool {
+	use zero_copy::TokenKind as Zc;
+
+	match (e, a) {
+		(TokenKind::Eof,         Zc::Eof)         => return true,
+		(TokenKind::LParen,      Zc::LParen)      => return true,
+		(TokenKind::RParen,      Zc::RParen)      => return true,
+		(TokenKind::Quote,       Zc::Quote)       => return true,
+		(TokenKind::Dot,         Zc::Dot)         => return true,
+		(TokenKind::Id(e),       Zc::Id(a))       => return e.as_str() == a,
+		(TokenKind::IntLit(e),   Zc::IntLit(a))   => return e == a,
+		(TokenKind::FloatLit(e), Zc::FloatLit(a)) => return e == a,
+		(TokenKind::StrLit(e),   Zc::StrLit(a))   => return e == a,
+		(TokenKind::Comment(e),  Zc::Comment(a))  => return e == a,
+		_                                         => return false,
+	}
+}
+
+// Makes a big program out of a few kinds of lines, with a good mix of every kind of token.
+fn generate_input(lines: usize) -> String {
+	let mut ret = String::new();
+
+	for i in 0 .. lines {
+		match i % 5 {
+			0 => ret += &format!("(define (function_{} x y) (add x (mul y {})))\n", i, i),
+			1 => ret += &format!("  (print \"line {} of the input\" 0x{:X} 1_000_{:03})\n", i, i, i % 1000),
+			2 => ret += &format!("  ; a comment about line {}\n", i),
+			3 => ret += &format!("  (let ((π {}.5e-3) (naïve_{} \"esc\\n\")) (sub π naïve_{}))\n", i, i, i),
+			_ => ret += &format!("#| block #| nested |# comment |# (list a b c {})\n", i),
+		}
+	}
+
+	return ret;
+}
+
+fn best_time(mut f: impl FnMut() -> usize) -> Duration {
+	let mut best = Duration::MAX;
+
+	for _ in 0 .. RUNS {
+		let start = Instant::now();
+		black_box(f());
+		best = best.min(start.elapsed());
+	}
+
+	return best;
+}
+
+fn report(name: &str, mb: f64, time: Duration) {
+	println!("{:28} {:8.2} ms {:8.2} MB/s", name, time.as_secs_f64() * 1000.0, mb / time.as_secs_f64());
+}
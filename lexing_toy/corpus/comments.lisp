@@ -0,0 +1,10 @@
+; a line comment on its own line
+(define x 10) ; a comment at the end of a line
+
+#| a block comment
+   that spans lines #| and has a nested one |#
+   inside it |#
+(print   x	#| tabs and spaces |#   "x is ten")
+
+
+; a comment at the end of the file, with no newline after it
\ No newline at end of file
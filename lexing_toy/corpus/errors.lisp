@@ -0,0 +1,6 @@
+; every line in here has a mistake, but it still has to round-trip!
+(add 1 $ 2)
+(0x 12abc 1.e5)
+(bad "escape \q")
+(unclosed (parens
+"unterminated string
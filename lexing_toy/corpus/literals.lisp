@@ -0,0 +1,3 @@
+(list 0xFF 0b1010_1010 0o777 1_000_000)
+(list 3.14 1e10 2.5E-3 0.5)
+(list "hello" "tab\tnewline\n" "quote \" backslash \\" "\u{1F600}")
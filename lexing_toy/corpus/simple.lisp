@@ -0,0 +1,2 @@
+(add 1 2)
+(mul (sub 10 4) (div 100 5))
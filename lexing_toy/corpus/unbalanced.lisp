@@ -0,0 +1,3 @@
+)) extra closing parens
+(and an #| unterminated
+block comment
@@ -0,0 +1,4 @@
+; identifiers and strings don't have to be ASCII.
+(définir café "☕")
+(λ (x) (mul x x))
+("日本語" "emoji 😀 in a string")
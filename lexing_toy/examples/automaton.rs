@@ -0,0 +1,51 @@
+// Shows the lexer generator's automata. By default it uses simple_rules() (parens, identifiers,
+// and integers), which are small enough to draw; add --full to use all of lisp_rules().
+//
+//     cargo run --example automaton -- nfa > nfa.dot     # the NFA in Graphviz DOT format
+//     cargo run --example automaton -- dfa > dfa.dot     # the minimized DFA in DOT format
+//     cargo run --example automaton -- trace "(add 1 2)" # every state transition for some code
+//
+// Then "dot -Tsvg dfa.dot -o dfa.svg" turns a DOT file into a picture.
+//
+// The hand-written lexer (lexing_toy::lex) doesn't have an automaton of its own to show, but these
+// rules describe the same tokens, so it's the same automaton. trace also prints the tokens that
+// the hand-written lexer gives for the code, so you can see that they match.
+
+use lexing_toy::lexgen::*;
+
+const USAGE: &str = "usage: automaton [--full] (nfa | dfa | trace CODE)";
+
+fn main() {
+	let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+	let rules = match args.iter().position(|a| a == "--full") {
+		Some(i) => {
+			args.remove(i);
+			lisp_rules()
+		}
+
+		None => simple_rules(),
+	};
+
+	let lexer = TableLexer::new(rules).expect("the rules are all valid regexes");
+
+	match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+		["nfa"]         => print!("{}", lexer.nfa_dot()),
+		["dfa"]         => print!("{}", lexer.dfa_dot()),
+		["trace", code] => {
+			print!("{}", lexer.trace(code));
+			println!();
+			println!("the hand-written lexer gives:");
+
+			match lexing_toy::lex(code) {
+				Ok(tokens) => tokens.iter().for_each(|t| println!("    {:?} at {}", t.kind, t.span)),
+				Err(e)     => println!("    error: {}", e),
+			}
+		}
+
+		_               => {
+			eprintln!("{}", USAGE);
+			std::process::exit(2);
+		}
+	}
+}
@@ -0,0 +1,73 @@
+// Shows indentation mode (see src/indent.rs). It lexes some code with Lexer::with_indentation()
+// and prints the tokens one line at a time, so you can see where the Newline, Indent, and Dedent
+// tokens go. Run it with:
+//
+//     cargo run --example indent            # some example code
+//     cargo run --example indent -- FILE    # your own code
+//
+// Any errors (like a badly-indented line) are shown after the tokens.
+
+use lexing_toy::*;
+
+const EXAMPLE: &str = "\
+define (fact n)
+    if (le n 1)
+        1
+        ; a comment doesn't count as a line
+  #| and neither does a block comment, however it's indented |#
+
+        mul n (fact (sub n 1))
+print (fact 5)
+print (add 1
+           2)   ; inside parens, indentation doesn't matter
+let ((x 5))
+    print x
+  print x
+";
+
+fn main() {
+	let (name, source) = match std::env::args().nth(1) {
+		Some(file) => {
+			let source = std::fs::read_to_string(&file).expect("couldn't read the file");
+			(file, source)
+		}
+
+		None => ("<example>".to_string(), EXAMPLE.to_string()),
+	};
+
+	println!("{}", source);
+
+	let mut lexer = Lexer::new_recovering(&source).with_indentation();
+	let mut line  = String::new();
+
+	for t in lexer.by_ref() {
+		let t = t.expect("recovering lexer never fails");
+
+		let text = match &t.kind {
+			TokenKind::Newline     => "NEWLINE".to_string(),
+			TokenKind::Indent      => "INDENT".to_string(),
+			TokenKind::Dedent      => "DEDENT".to_string(),
+			TokenKind::Eof         => "EOF".to_string(),
+			TokenKind::LParen      => "(".to_string(),
+			TokenKind::RParen      => ")".to_string(),
+			TokenKind::Id(sym)     => sym.to_string(),
+			TokenKind::IntLit(i)   => i.to_string(),
+			TokenKind::Error       => "ERROR".to_string(),
+			other                  => format!("{:?}", other),
+		};
+
+		line += &text;
+		line += " ";
+
+		// start a new line of output after each Newline, like in the source.
+		if t.kind == TokenKind::Newline || t.kind == TokenKind::Eof {
+			println!("{}", line.trim_end());
+			line.clear();
+		}
+	}
+
+	for e in lexer.take_errors() {
+		println!();
+		print!("{}", e.to_diagnostic(lexer.source_map()).render(&name, &source));
+	}
+}
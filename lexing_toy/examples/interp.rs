@@ -0,0 +1,59 @@
+// Shows interpolation mode (see src/interp.rs). It lexes some code with
+// Lexer::with_interpolation() and prints the tokens, indented to show the mode stack. Run it with:
+//
+//     cargo run --example interp                              # some examples
+//     cargo run --example interp -- '"hello ${name}!"'        # your own code
+//
+// Any errors are shown after the tokens.
+
+use lexing_toy::*;
+
+const EXAMPLES: &[&str] = &[
+	r#"(print "hello ${name}!")"#,
+	r#""a ${f "b ${x} c"} d""#,
+	r#""${(add 1 2)} is ${"three"}, and \${this} isn't interpolated""#,
+	r#""never closed: ${(add 1 "#,
+];
+
+fn main() {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+	if args.is_empty() {
+		for (i, code) in EXAMPLES.iter().enumerate() {
+			show(&format!("<example {}>", i + 1), code);
+		}
+	} else {
+		for code in &args {
+			show("<argument>", code);
+		}
+	}
+}
+
+fn show(name: &str, code: &str) {
+	println!("{}", code);
+
+	let mut lexer = Lexer::new_recovering(code).with_interpolation();
+	let mut depth = 1;
+
+	for t in lexer.by_ref() {
+		let t = t.expect("recovering lexer never fails");
+
+		// the ends go one level out *before* they're printed, and the starts go one level in
+		// *after*, so each start lines up with its end.
+		if let TokenKind::InterpEnd | TokenKind::StrEnd = t.kind {
+			depth -= 1;
+		}
+
+		println!("{}{:?}", "    ".repeat(depth), t.kind);
+
+		if let TokenKind::InterpStart | TokenKind::StrStart = t.kind {
+			depth += 1;
+		}
+	}
+
+	for e in lexer.take_errors() {
+		print!("{}", e.to_diagnostic(lexer.source_map()).render(name, code));
+	}
+
+	println!();
+}
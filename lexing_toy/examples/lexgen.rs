@@ -0,0 +1,79 @@
+// Shows off the lexer generator: what it builds from lisp_rules(), where it can't do what the
+// hand-written lexer does, and how fast it is. Run it with:
+//
+//     cargo run --release --example lexgen
+//
+// (tests/lexgen.rs checks that the two lexers give the same tokens.)
+
+use std::time::Instant;
+
+use lexing_toy::lexgen::*;
+use lexing_toy::*;
+
+fn main() {
+	let start = Instant::now();
+	let lexer = lisp_lexer();
+	let time  = start.elapsed();
+
+	println!("built a lexer from {} rules in {:?}:", lexer.rules().len(), time);
+	println!("    {} NFA states", lexer.nfa().states.len());
+	println!("    {} character classes", lexer.alphabet().num_classes());
+	println!("    {} DFA states, {} after minimizing",
+		lexer.unminimized_states(), lexer.dfa().num_states());
+	println!();
+
+	// the two lexers agree on normal code (tests/lexgen.rs checks that). nested comments are where
+	// they don't, since regexes can't count.
+	let nested = "#| outer #| inner |# still outer |# x";
+	println!("the two lexers disagree on {:?}:", nested);
+	println!("    hand-written: {}", show(lex(nested)));
+	println!("    generated:    {}", show(lexer.lex(nested)));
+	println!();
+
+	// rule priority: "define" matches both rules with the same length, so the first one wins.
+	// but "defined" is longer as an Id, so longest match wins there.
+	let with_keyword = TableLexer::new(vec![
+		Rule::skip("Whitespace", "[ \\t\\n]+"),
+		Rule::token("Define", "define", |_, _| Ok(TokenKind::StrLit("<the define keyword>".into()))),
+		Rule::token("Id", "[a-z]+", |text, _| Ok(TokenKind::Id(Symbol::intern(text)))),
+	]).expect("these are valid regexes");
+
+	println!("with a keyword rule before the Id rule:");
+	println!("    {}", show(with_keyword.lex("define defined")));
+	println!();
+
+	speed(&lexer);
+}
+
+// The tokens as a string, so they're easy to compare and print.
+fn show(tokens: Result<Vec<Token>, LexError>) -> String {
+	match tokens {
+		Ok(tokens) => {
+			let kinds = tokens.iter().map(|t| format!("{:?}@{}", t.kind, t.span)).collect::<Vec<_>>();
+			return kinds.join(" ");
+		}
+
+		Err(e) => return format!("error: {}", e),
+	}
+}
+
+// How fast is a table-driven lexer compared to the hand-written one?
+fn speed(lexer: &TableLexer) {
+	let mut source = String::new();
+
+	for i in 0 .. 20_000 {
+		source += &format!("(define (f{} x) (add x {} 0x{:X} 1.5e3 \"str {}\")) ; line {}\n", i, i, i, i, i);
+	}
+
+	let start = Instant::now();
+	let hand  = lex(&source).expect("valid code");
+	let hand_time = start.elapsed();
+
+	let start = Instant::now();
+	let generated = lexer.lex(&source).expect("valid code");
+	let generated_time = start.elapsed();
+
+	assert_eq!(hand.len(), generated.len());
+	println!("lexing {} KB: hand-written took {:?}, generated took {:?}.",
+		source.len() / 1024, hand_time, generated_time);
+}
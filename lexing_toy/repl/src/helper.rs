@@ -0,0 +1,124 @@
+// This is the "helper" for the rustyline Editor. It uses the lexer to color in the code as you
+// type it, and it keeps the prompt open if you press Enter before closing all your parens.
+// It's a nice example of a lexer being used for something other than compiling!
+
+use std::borrow::Cow;
+
+use colored::Colorize;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ ValidationContext, ValidationResult, Validator };
+use rustyline::Helper;
+
+use lexing_toy::*;
+
+pub struct LispHelper;
+
+// rustyline wants all four of these traits implemented. we only care about Highlighter and
+// Validator, so the other two are empty, and use the "default" methods from the traits.
+impl Helper for LispHelper {}
+
+impl Completer for LispHelper {
+	type Candidate = String;
+}
+
+impl Hinter for LispHelper {
+	type Hint = String;
+}
+
+impl Highlighter for LispHelper {
+	// This is called to draw the line. It returns a copy of the line with color codes in it.
+	fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+		// commands aren't code, so don't color them like code.
+		if line.trim_start().starts_with(':') {
+			return Cow::Borrowed(line);
+		}
+
+		// recovering mode, since the code is usually "wrong" while you're in the middle of typing
+		// it, and we want to color everything after the mistake too. comments are kept so they
+		// can be colored.
+		let map    = SourceMap::new(line);
+		let tokens = Lexer::new_recovering(line).with_comments().map(|t| t.expect("recovering"));
+
+		let mut ret  = String::new();
+		let mut last = 0; // the byte offset where the last token ended.
+
+		for t in tokens {
+			let range = map.byte_range(t.span);
+
+			// the whitespace between tokens is copied as-is.
+			ret += &line[last .. range.start];
+
+			let text = &line[range.clone()];
+
+			let colored = match t.kind {
+				TokenKind::LParen | TokenKind::RParen => text.bright_blue(),
+				TokenKind::Quote | TokenKind::Dot     => text.bright_blue(),
+				TokenKind::Id(..)                     => text.normal(),
+				TokenKind::IntLit(..)                 => text.yellow(),
+				TokenKind::FloatLit(..)               => text.bright_yellow(),
+				TokenKind::StrLit(..)                 => text.green(),
+				TokenKind::Comment(..)                => text.bright_black(),
+				TokenKind::Error                      => text.on_red(),
+				// the helper doesn't use indentation or interpolation mode, so these never show up.
+				TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent |
+				TokenKind::StrStart | TokenKind::StrPart(..) | TokenKind::InterpStart |
+				TokenKind::InterpEnd | TokenKind::StrEnd => text.normal(),
+				TokenKind::Eof                        => break,
+			};
+
+			ret += &colored.to_string();
+			last = range.end;
+		}
+
+		ret += &line[last ..];
+		return Cow::Owned(ret);
+	}
+
+	// This is asked whenever a character is typed or the cursor moves. Returning true means
+	// "redraw the line," which we want every time, since any character can change the colors.
+	fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+		return true;
+	}
+}
+
+impl Validator for LispHelper {
+	// This is called when you hit Enter. If it says the input is Incomplete, the Enter puts in
+	// a newline instead of submitting the code.
+	fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+		let input = ctx.input();
+
+		if input.trim_start().starts_with(':') {
+			return Ok(ValidationResult::Valid(None));
+		}
+
+		let (tokens, errors) = lex_recovering(input);
+
+		// a string or comment that hasn't been closed yet means you're probably still typing it.
+		for e in &errors {
+			if let LexError::UnterminatedString(..) | LexError::UnterminatedComment(..) = e {
+				return Ok(ValidationResult::Incomplete);
+			}
+		}
+
+		// count how many parens are open. we use the tokens instead of just counting '(' and ')'
+		// characters, because parens inside strings and comments don't count!
+		let mut depth = 0;
+
+		for t in &tokens {
+			match t.kind {
+				TokenKind::LParen => depth += 1,
+				TokenKind::RParen => depth -= 1,
+				_                 => {}
+			}
+		}
+
+		// if there are *too many* ')', let it through, and the parser will complain about it.
+		if depth > 0 {
+			return Ok(ValidationResult::Incomplete);
+		} else {
+			return Ok(ValidationResult::Valid(None));
+		}
+	}
+}
@@ -0,0 +1,357 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+use colored::Colorize;
+use rustyline::{ Editor, KeyEvent, KeyCode, Modifiers, Cmd, error::ReadlineError };
+
+use eval_lisp::Interpreter;
+use lexing_toy::*;
+
+// the syntax highlighting and paren-matching for the prompt.
+mod helper;
+
+use helper::LispHelper;
+
+// ------------------------------------------------------------------------------------------------
+// Stages
+// ------------------------------------------------------------------------------------------------
+
+// The input goes through a "pipeline": lex it, parse it, then evaluate it. A Stage says how far
+// down the pipeline to go, and what to show the user at the end.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Stage {
+	Tokens, // just lex it and show the tokens.
+	Sexp,   // parse it and print the AST back out as an S-expression.
+	Ast,    // parse it and show the AST's structure.
+	Eval,   // parse it and evaluate it.
+
+	// these two just lex it, like Tokens, but print the tokens as JSON for other programs to use.
+	Json,      // one JSON array of all the tokens.
+	JsonLines, // one JSON object per line.
+}
+
+impl Stage {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"tokens" => return Some(Stage::Tokens),
+			"sexp"   => return Some(Stage::Sexp),
+			"ast"    => return Some(Stage::Ast),
+			"eval"   => return Some(Stage::Eval),
+			"json"   => return Some(Stage::Json),
+			"jsonl"  => return Some(Stage::JsonLines),
+			_        => return None,
+		}
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// main
+// ------------------------------------------------------------------------------------------------
+
+const USAGE: &str = "\
+usage: lexing_toy [--tokens | --sexp | --ast | --eval | --json | --jsonl] [FILE]
+
+With no FILE, starts an interactive prompt. With a FILE, runs it through the pipeline once and
+exits. Use - as the FILE to read from standard input instead.
+
+--json prints the tokens as a JSON array, and --jsonl prints them as JSON Lines (one token per
+line). If there is a lexing error, a JSON error object is printed instead, and the exit code is 1.";
+
+fn main() {
+	// std::env::args() gives us the command-line arguments. the first one is the program's
+	// name, so we skip it.
+	let mut stage = Stage::Tokens;
+	let mut file  = None;
+
+	for arg in std::env::args().skip(1) {
+		if arg == "-h" || arg == "--help" {
+			println!("{}", USAGE);
+			return;
+		} else if let Some(name) = arg.strip_prefix("--") {
+			match Stage::from_name(name) {
+				Some(s) => stage = s,
+				None    => usage_error(&format!("unknown option '{}'", arg)),
+			}
+		} else if file.is_none() {
+			file = Some(arg);
+		} else {
+			usage_error("only one file can be given");
+		}
+	}
+
+	match file {
+		// the exit code tells whoever ran us (like a shell script) whether it worked.
+		Some(file) => {
+			let ok = run_file(&file, stage, &mut Interpreter::new());
+			std::process::exit(if ok { 0 } else { 1 });
+		}
+
+		None => repl(stage),
+	}
+}
+
+fn usage_error(msg: &str) -> ! {
+	eprintln!("{} {}", "Error:".red(), msg);
+	eprintln!("{}", USAGE);
+	std::process::exit(2);
+}
+
+// ------------------------------------------------------------------------------------------------
+// The interactive prompt
+// ------------------------------------------------------------------------------------------------
+
+fn repl(mut stage: Stage) {
+	// this Editor stuff just makes it much nicer to use this program on the command line.
+	// it's not important for understanding the lexer at all.
+	let mut rl = Editor::<LispHelper>::new();
+	rl.set_helper(Some(LispHelper));
+	rl.bind_sequence(KeyEvent(KeyCode::Tab, Modifiers::NONE),   Cmd::Insert(1, "\t".into()));
+	rl.bind_sequence(KeyEvent(KeyCode::Down, Modifiers::SHIFT), Cmd::Insert(1, "\n".into()));
+
+	// the history is saved in a file, so that the up arrow can bring back things you typed the
+	// last time you ran the program. it's fine if the file doesn't exist yet.
+	let history = history_path();
+	let _ = rl.load_history(&history);
+
+	// one interpreter for the whole session, so that what you define stays defined.
+	let mut interp = Interpreter::new();
+
+	println!("{}",
+		"--------------------------------------------------------------------------".bright_blue());
+	println!("{}",
+		"Type some code. Shift+Down inserts a newline; Enter submits; Ctrl+C quits.".bright_blue());
+	println!("{}",
+		"Enter also inserts a newline if there are unclosed parens.                ".bright_blue());
+	println!("{}",
+		"Type :help to see the commands.                                           ".bright_blue());
+	println!("{}",
+		"--------------------------------------------------------------------------".bright_blue());
+
+	loop {
+		let line = rl.readline(">> ");
+		match line {
+			// we got a line of text!
+			Ok(line) => {
+				rl.add_history_entry(line.as_str());
+
+				if let Some(command) = line.trim_start().strip_prefix(':') {
+					if !run_command(command, &mut stage, &mut interp) {
+						break;
+					}
+				} else if !line.trim().is_empty() {
+					run(&line, "<input>", stage, &mut interp);
+				}
+			}
+			// they hit ctrl+C.
+			Err(ReadlineError::Interrupted) => break,
+			// they hit ctrl+D.
+			Err(ReadlineError::Eof) => break,
+			// some other error happened.
+			Err(err) => {
+				println!("Error: {:?}", err);
+				break
+			}
+		}
+	}
+
+	if let Err(err) = rl.save_history(&history) {
+		println!("{} couldn't save history to {}: {}", "Warning:".yellow(), history.display(), err);
+	}
+
+	println!("byeeeeee!");
+}
+
+// The history goes in your home directory, or the current directory if there's no $HOME.
+fn history_path() -> PathBuf {
+	let dir = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+	return dir.join(".lexing_toy_history");
+}
+
+const HELP: &str = "\
+Commands:
+    :tokens [CODE]   show the tokens (this is what happens when you start)
+    :sexp   [CODE]   parse, and print the AST as an S-expression
+    :ast    [CODE]   parse, and show the AST's structure
+    :eval   [CODE]   parse, and evaluate (see eval_lisp for what it understands)
+    :json   [CODE]   show the tokens as JSON
+    :jsonl  [CODE]   show the tokens as JSON Lines
+    :load   FILE     run the code in FILE
+    :help            show this message
+    :quit            quit
+
+:tokens, :sexp, :ast, :eval, :json, and :jsonl with no CODE switch what happens to everything you type after.
+With CODE, they only run that code, and don't switch.";
+
+// Runs a command (the part after the ':'). Returns false if the program should quit.
+fn run_command(command: &str, stage: &mut Stage, interp: &mut Interpreter) -> bool {
+	// split_once splits the command name from the rest of the line (if there is any).
+	let (name, arg) = match command.trim().split_once(char::is_whitespace) {
+		Some((name, arg)) => (name, arg.trim()),
+		None              => (command.trim(), ""),
+	};
+
+	match name {
+		"help"        => println!("{}\n", HELP),
+		"quit" | "q"  => return false,
+
+		"load" => {
+			if arg.is_empty() {
+				println!("{} :load needs a file name\n", "Error:".red());
+			} else {
+				run_file(arg, *stage, interp);
+			}
+		}
+
+		_ => match Stage::from_name(name) {
+			Some(s) if arg.is_empty() => {
+				*stage = s;
+				println!("{} {:?}\n", "Now showing:".green(), s);
+			}
+
+			Some(s) => {
+				run(arg, "<input>", s, interp);
+			}
+
+			None => println!("{} unknown command ':{}' (try :help)\n", "Error:".red(), name),
+		}
+	}
+
+	return true;
+}
+
+// ------------------------------------------------------------------------------------------------
+// The pipeline
+// ------------------------------------------------------------------------------------------------
+
+// Reads a file (or stdin, if the name is "-") and runs it. Returns true if it worked.
+fn run_file(name: &str, stage: Stage, interp: &mut Interpreter) -> bool {
+	let source = if name == "-" {
+		let mut s = String::new();
+		std::io::stdin().read_to_string(&mut s).map(|_| s)
+	} else {
+		std::fs::read_to_string(name)
+	};
+
+	match source {
+		Ok(source) => return run(&source, name, stage, interp),
+		Err(err) => {
+			println!("{} couldn't read {}: {}\n", "Error:".red(), name, err);
+			return false;
+		}
+	}
+}
+
+// Runs some source code through the pipeline up to the given stage, and shows the result.
+// file_name is only used in error messages, and interp is what evaluates the code in the Eval
+// stage. Returns true if there were no errors.
+fn run(source: &str, file_name: &str, stage: Stage, interp: &mut Interpreter) -> bool {
+	if stage == Stage::Json || stage == Stage::JsonLines {
+		return run_json(source, stage);
+	}
+
+	// Stage 1: lexing. a recovering lexer keeps going after errors, so we get *all* the tokens
+	// and *all* the errors at once, instead of having to fix them one at a time. (this is what
+	// lex_recovering() does, but we also want the warnings out of the lexer.)
+	let mut lexer = Lexer::new_recovering(source);
+	let tokens    = lexer.by_ref().map(|t| t.expect("recovering lexer never fails")).collect::<Vec<_>>();
+	let errors    = lexer.take_errors();
+	let warnings  = lexer.take_warnings();
+	let map       = lexer.source_map();
+
+	if stage == Stage::Tokens {
+		println!("{} ", "Tokens:".green());
+
+		for t in &tokens {
+			println!("   {:?}", t);
+		}
+	}
+
+	// warnings don't stop anything, but they're worth showing.
+	for w in &warnings {
+		println!();
+		print!("{}", w.to_diagnostic(map).render(file_name, source));
+	}
+
+	// if there were any errors, print them out, pointing at the bad code. we can't go on to
+	// parsing if the tokens are bad.
+	if !errors.is_empty() {
+		for e in errors {
+			println!();
+			print!("{}", e.to_diagnostic(map).render(file_name, source));
+		}
+
+		println!();
+		return false;
+	}
+
+	if stage == Stage::Tokens {
+		println!();
+		return true;
+	}
+
+	// Stage 2: parsing. the parser uses the same Token type as the lexer (from the tokens
+	// crate), so it can read our tokens as-is. like the lexer, it recovers from errors, so we
+	// see all of them at once. parse errors know which token they're about, so they can point
+	// at it too.
+	let (ast, errors) = parsing_lisp::Parser::parse_recovering(&tokens);
+
+	let ok = errors.is_empty();
+
+	for e in errors {
+		println!();
+		print!("{}", e.to_diagnostic(map).render(file_name, source));
+	}
+
+	if !ok {
+		println!();
+
+		// there's no sense evaluating broken code. but the AST is still worth a look, since it
+		// shows how the parser patched things up (with Errors where things were missing).
+		if stage == Stage::Eval {
+			return false;
+		}
+	}
+
+	// Stage 3: show the AST, or evaluate it. a program can have several expressions in it, so
+	// each one gets its own line.
+	for exp in &ast {
+		match stage {
+			Stage::Tokens | Stage::Json | Stage::JsonLines => unreachable!(),
+			Stage::Sexp   => println!("{}", exp),
+			Stage::Ast    => println!("{:#?}", exp),
+			Stage::Eval   => match interp.eval(exp) {
+				Ok(value) => println!("{}", value),
+				Err(msg)  => {
+					println!("{} {}\n", "Runtime error:".red(), msg);
+					return false;
+				}
+			}
+		}
+	}
+
+	println!();
+	return ok;
+}
+
+// The JSON version of the Tokens stage. Everything goes to stdout as JSON, even errors, so that
+// the program reading it only has to understand one format. Returns true if there were no errors.
+fn run_json(source: &str, stage: Stage) -> bool {
+	// no recovering here; a tool that wants to know about errors only needs the first one, and
+	// the Error tokens from recovering wouldn't mean much to it anyway.
+	match lex(source) {
+		Ok(tokens) => {
+			if stage == Stage::Json {
+				println!("{}", json::tokens_to_json(&tokens));
+			} else {
+				print!("{}", json::tokens_to_json_lines(&tokens));
+			}
+
+			return true;
+		}
+
+		Err(err) => {
+			println!("{}", json::error_to_json(&err));
+			return false;
+		}
+	}
+}
@@ -0,0 +1,184 @@
+// This file has an *incremental* relexer. A text editor that highlights code (or shows errors)
+// has to know the tokens, and the code changes every time you press a key. Lexing the whole file
+// again on every keystroke works, but it's a lot of wasted work: one keystroke only changes the
+// tokens right around it, and everything else stays the same (it just moves over a bit).
+//
+// So a Relexer keeps the *old* tokens, and when an edit is made, it only lexes the new tokens
+// around the edit. As soon as it finds a token that starts at the same place as an old token did
+// (after accounting for the text that was added or removed), it stops lexing. Everything after
+// that must be the same as before, since the lexer only looks at the text from a token onward,
+// and that text didn't change! So the rest of the old tokens are copied over, with their spans
+// shifted to where they are now.
+//
+// The tokens here are the ones you get from lex_recovering() (no comments, with Error tokens),
+// and after each edit, they're exactly what lex_recovering() would have given for the new source.
+
+use std::ops::Range;
+
+use crate::*;
+
+// ------------------------------------------------------------------------------------------------
+// Edit
+// ------------------------------------------------------------------------------------------------
+
+// An edit replaces the characters in range (codepoint indices, like Span) with text. Typing a
+// character is an edit with an empty range; deleting is an edit with empty text.
+#[derive(Debug, Clone)]
+pub struct Edit {
+	pub range: Range<usize>,
+	pub text:  String,
+}
+
+impl Edit {
+	pub fn new(range: Range<usize>, text: &str) -> Self {
+		return Edit { range, text: text.into() };
+	}
+
+	// Makes the edit to some source code, giving the new source code.
+	pub fn apply(&self, source: &str) -> String {
+		let map   = SourceMap::new(source);
+		let start = map.char_to_byte(self.range.start);
+		let end   = map.char_to_byte(self.range.end);
+		return format!("{}{}{}", &source[.. start], self.text, &source[end ..]);
+	}
+
+	// How many codepoints longer the source got. Negative if it got shorter.
+	pub fn delta(&self) -> isize {
+		return self.text.chars().count() as isize - self.range.len() as isize;
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Relexing
+// ------------------------------------------------------------------------------------------------
+
+// A Relexer keeps everything about the source code that it needs from one edit to the next: the
+// source, its tokens, and the Lexer's two copies of it (the codepoints, and the SourceMap). An
+// edit changes all four of those in place, so only the part of the source around the edit is
+// lexed or measured again. (the codepoints and offsets *after* the edit still have to be moved
+// over, but that's just copying numbers, which is a lot faster than lexing.)
+pub struct Relexer {
+	source: String,
+	tokens: Vec<Token>,
+	chars:  Vec<char>,
+	map:    SourceMap,
+}
+
+impl Relexer {
+	// Lexes the whole source the first time, the same as lex_recovering().
+	pub fn new(source: &str) -> Self {
+		let mut lexer = Lexer::new_recovering(source);
+		let tokens    = lexer.by_ref().map(|t| t.expect("recovering lexers never return Err")).collect();
+		let (chars, map) = lexer.into_parts();
+		return Relexer { source: source.into(), tokens, chars, map };
+	}
+
+	pub fn source(&self) -> &str {
+		return &self.source;
+	}
+
+	// The tokens for the current source, same as lex_recovering(self.source()).0.
+	pub fn tokens(&self) -> &[Token] {
+		return &self.tokens;
+	}
+
+	pub fn source_map(&self) -> &SourceMap {
+		return &self.map;
+	}
+
+	// Makes the edit, and relexes around it. Returns how many tokens actually had to be lexed;
+	// the rest are the old tokens, moved over.
+	pub fn edit(&mut self, edit: &Edit) -> usize {
+		let delta = edit.delta();
+
+		// first, update the source, and the Lexer's codepoints and SourceMap for it.
+		let byte_range = self.map.char_to_byte(edit.range.start) .. self.map.char_to_byte(edit.range.end);
+		self.source.replace_range(byte_range, &edit.text);
+		self.chars.splice(edit.range.clone(), edit.text.chars());
+		self.map.edit(&self.source, edit.range.clone(), &edit.text);
+
+		// Step 1: find where to start lexing. the lexer decides where a token ends by looking at
+		// the character just past it, so the first token that ends at or after the edit might
+		// change. we start at the token *before* that one, to be safe. everything before that is
+		// kept as-is.
+		// (the tokens are in order, so this can be a binary search too. the Eof token ends at the
+		// end of the source, so some token is always touched.)
+		let touched = self.tokens.partition_point(|t| t.span.end < edit.range.start);
+		let keep    = touched.saturating_sub(1);
+		let restart = if keep == 0 { 0 } else { self.tokens[keep].span.start };
+
+		// Step 2: lex new tokens until one starts where an old token did. "edit_end" is where the
+		// new text ends, in the new source; a token before that can't be an old token. the Lexer
+		// borrows the codepoints and SourceMap, and gives them back at the end.
+		let edit_end   = edit.range.start + edit.text.chars().count();
+		let chars      = std::mem::take(&mut self.chars);
+		let map        = std::mem::replace(&mut self.map, SourceMap::new(""));
+		let mut lexer  = Lexer::from_parts(chars, map).starting_at(restart);
+		let mut new    = vec![];
+		let mut reused = None;
+		lexer.recover  = true;
+
+		for t in &mut lexer {
+			let t = t.expect("recovering lexers never return Err");
+
+			if t.span.start >= edit_end {
+				// where this token would have been in the old source.
+				let old_start = (t.span.start as isize - delta) as usize;
+
+				// the old tokens are sorted by position, so we can binary search for it.
+				if let Ok(i) = self.tokens.binary_search_by_key(&old_start, |o| o.span.start) {
+					reused = Some((i, t.span));
+					break;
+				}
+			}
+
+			new.push(t);
+		}
+
+		let (chars, map) = lexer.into_parts();
+		self.chars = chars;
+		self.map   = map;
+
+		// Step 3: put the new tokens in place of the old ones they replace. if we found an old
+		// token, it and the rest after it are moved to where they are now. if not, that's fine,
+		// it just means the edit changed everything after it (like typing a '"' that starts a
+		// string).
+		let lexed = new.len() + reused.is_some() as usize; // (the old token we found was lexed too.)
+
+		match reused {
+			Some((i, first)) => {
+				shift_tokens(&mut self.tokens[i ..], first, delta);
+				self.tokens.splice(keep .. i, new);
+			}
+
+			None => {
+				self.tokens.truncate(keep);
+				self.tokens.extend(new);
+			}
+		}
+
+		return lexed;
+	}
+}
+
+// Moves the old tokens to where they are after the edit. first is the new Span of tokens[0],
+// which tells us how far the line and column moved.
+fn shift_tokens(tokens: &mut [Token], first: Span, delta: isize) {
+	// lines after the edit move up or down by the same amount. but tokens on the *same* line as
+	// the first one also move left or right, since the edit happened on that line.
+	let line_delta = first.line as isize - tokens[0].span.line as isize;
+	let col_delta  = first.col  as isize - tokens[0].span.col  as isize;
+	let first_line = tokens[0].span.line;
+
+	for t in tokens {
+		let span = &mut t.span;
+		span.start = (span.start as isize + delta) as usize;
+		span.end   = (span.end   as isize + delta) as usize;
+
+		if span.line == first_line {
+			span.col = (span.col as isize + col_delta) as usize;
+		}
+
+		span.line = (span.line as isize + line_delta) as usize;
+	}
+}
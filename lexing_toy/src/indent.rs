@@ -0,0 +1,262 @@
+// Indentation mode: lexing for languages that use the "offside rule," like Python and Haskell,
+// where indentation shows which lines are inside of which other lines:
+//
+//     define (square x)
+//         mul x x
+//     print (square 12)
+//
+// A parser can't easily look at indentation, so the lexer turns it into tokens. This is how
+// Python's lexer does it:
+//
+// - a Newline token comes at the end of every line that had tokens on it.
+// - the lexer keeps a *stack* of indentation levels, starting with just 0.
+// - at the start of each line, it measures the indentation:
+//     - if it's more than the top of the stack, that's an Indent token, and the new level is
+//       pushed.
+//     - if it's less, levels are popped until the top is the same as the line's indentation,
+//       and each pop is a Dedent token. if it never comes out the same, the line is wrongly
+//       indented, like the third line here:
+//
+//         a
+//             b
+//           c      <- 2 isn't 0 or 4!
+//
+// - at the end of the input, there's one Dedent for each level still on the stack (except 0).
+//
+// So Indent and Dedent work just like '(' and ')' - they always come in pairs. Some lines don't
+// count, though:
+//
+// - blank lines and lines with only comments (line or block comments) don't have tokens, so their
+//   indentation doesn't matter and they don't get a Newline.
+// - inside parentheses, newlines and indentation are ignored, just like normal. This lets you
+//   split one long line into several. (Python does this too.)
+//
+// One more rule: the indentation can use spaces or tabs, but not both. How wide is a tab? Every
+// text editor has a different answer! So instead of guessing, we say it's an error to mix them,
+// either in one line or from one line to another.
+
+use std::collections::VecDeque;
+
+use crate::*;
+
+// The extra state the Lexer needs in indentation mode.
+pub(crate) struct Layout {
+	// the indentation stack. it always has at least the 0 at the bottom.
+	levels: Vec<usize>,
+
+	// whether this source is indented with spaces or tabs. it's None until the first indented
+	// line, which decides it.
+	indent_char: Option<char>,
+
+	// Indent and Dedent tokens that have been figured out, but not given out yet. (one line can
+	// have several Dedents, but the lexer can only give out one token at a time.)
+	pending: VecDeque<Token>,
+
+	// true when the next thing to lex is the beginning of a line.
+	at_line_start: bool,
+
+	// true if there have been tokens on the current line. only those lines get a Newline.
+	// (lex_token() sets this too, when it makes an Error token.)
+	pub(crate) line_has_tokens: bool,
+
+	// how many parens are open. newlines only matter when this is 0.
+	depth: usize,
+}
+
+impl Layout {
+	pub(crate) fn new() -> Self {
+		return Layout {
+			levels:          vec![0],
+			indent_char:     None,
+			pending:         VecDeque::new(),
+			at_line_start:   true,
+			line_has_tokens: false,
+			depth:           0,
+		};
+	}
+}
+
+impl Lexer {
+	// Makes the lexer give out Newline, Indent, and Dedent tokens (see the top of indent.rs). Like
+	// with_comments(), use it like Lexer::new(source).with_indentation().
+	pub fn with_indentation(mut self) -> Self {
+		self.layout = Some(Layout::new());
+		return self;
+	}
+
+	// True if a '\n' is a token instead of whitespace right now. (inside an interpolation, it's
+	// just like being inside parens.)
+	pub(crate) fn newlines_matter(&self) -> bool {
+		match &self.layout {
+			Some(layout) => return layout.depth == 0 && !self.in_interpolation(),
+			None         => return false,
+		}
+	}
+
+	// lex_token_inner() for indentation mode. It handles the newlines and indentation, and
+	// lets lex_token_inner() do the rest.
+	pub(crate) fn lex_layout_token(&mut self) -> Result<Token, LexError> {
+		loop {
+			let layout = self.layout.as_mut().expect("only called in indentation mode");
+
+			if let Some(t) = layout.pending.pop_front() {
+				return Ok(t);
+			}
+
+			if layout.at_line_start {
+				layout.at_line_start = false;
+				self.lex_indentation()?;
+				continue;
+			}
+
+			// a string's text (in interpolation mode) can have newlines in it, but they're part of
+			// the text, and not the end of a line.
+			if self.in_string_text() {
+				let t = self.lex_token_inner()?;
+				self.layout.as_mut().unwrap().line_has_tokens = true;
+				return Ok(t);
+			}
+
+			// comments are still given out as tokens if you asked for them.
+			if let Some(comment) = self.skip_trivia()? {
+				return Ok(comment);
+			}
+
+			// skip_trivia() stops at a '\n' when newlines matter, so this is the end of a line.
+			if self.cur() == Some('\n') {
+				let layout = self.layout.as_mut().unwrap();
+				let span   = self.map.span(self.pos, self.pos + 1);
+				self.pos  += 1;
+				layout.at_line_start = true;
+
+				if layout.line_has_tokens {
+					layout.line_has_tokens = false;
+					return Ok(Token::new(span, TokenKind::Newline));
+				}
+
+				// a blank line, or one with only comments on it. nothing to see here.
+				continue;
+			}
+
+			let t      = self.lex_token_inner()?;
+			let layout = self.layout.as_mut().unwrap();
+
+			match t.kind {
+				TokenKind::LParen     => layout.depth += 1,
+				TokenKind::RParen     => layout.depth = layout.depth.saturating_sub(1),
+				TokenKind::Comment(_) => return Ok(t), // comments don't count as tokens on the line.
+
+				// at the end, finish off the last line and close all the open levels before the
+				// Eof token, so that every Indent has a matching Dedent.
+				TokenKind::Eof => {
+					if layout.line_has_tokens {
+						layout.line_has_tokens = false;
+						layout.pending.push_back(Token::new(t.span, TokenKind::Newline));
+					}
+
+					while layout.levels.len() > 1 {
+						layout.levels.pop();
+						layout.pending.push_back(Token::new(t.span, TokenKind::Dedent));
+					}
+
+					layout.pending.push_back(t);
+					continue;
+				}
+
+				_ => {}
+			}
+
+			layout.line_has_tokens = true;
+			return Ok(t);
+		}
+	}
+
+	// Measures the indentation at the start of a line, and figures out the Indent and Dedent
+	// tokens for it.
+	fn lex_indentation(&mut self) -> Result<(), LexError> {
+		let start  = self.pos;
+		self.start = start; // so that in recovering mode, an Error token covers the indentation.
+
+		while let Some(' ') | Some('\t') = self.cur() {
+			self.pos += 1;
+		}
+
+		// a blank line, a line with only comments, or the end of the input. the indentation
+		// doesn't matter on those. (we'll be back at the start of the next line.)
+		if self.only_comments_left_on_line() {
+			return Ok(());
+		}
+
+		let indentation = &self.source[start .. self.pos];
+		let span        = self.map.span(start, self.pos);
+		let here        = self.map.span(self.pos, self.pos);
+		let layout      = self.layout.as_mut().unwrap();
+
+		// every character of the indentation has to be the same one that the first indented
+		// line used.
+		let mixed = match indentation.first() {
+			None     => false,
+			Some(&c) => {
+				let expected = *layout.indent_char.get_or_insert(c);
+				indentation.iter().any(|&c| c != expected)
+			}
+		};
+
+		// since there's only one kind of character, the indentation is just how many there are.
+		let width = indentation.len();
+		let top   = *layout.levels.last().unwrap();
+
+		if width > top {
+			layout.levels.push(width);
+			layout.pending.push_back(Token::new(span, TokenKind::Indent));
+		} else if width < top {
+			while *layout.levels.last().unwrap() > width {
+				layout.levels.pop();
+				layout.pending.push_back(Token::new(here, TokenKind::Dedent));
+			}
+
+			// if we went past it, this line doesn't line up with any of the lines before it.
+			// the Dedents are still given out, so that they match up with the Indents.
+			if *layout.levels.last().unwrap() != width && !mixed {
+				return Err(LexError::InconsistentDedent(span));
+			}
+		}
+
+		if mixed {
+			return Err(LexError::MixedIndentation(span));
+		}
+
+		return Ok(());
+	}
+
+	// Looks ahead to see if the rest of the line is just comments, like "#| this |# ; and this".
+	// A line comment is easy, since it goes to the end of the line. But after a block comment
+	// there could be a token, so we have to skip over it to find out. (this doesn't move the
+	// lexer. the comments will be lexed for real after the indentation.)
+	fn only_comments_left_on_line(&mut self) -> bool {
+		let saved = self.pos;
+
+		let ret = loop {
+			while let Some(' ') | Some('\t') = self.cur() {
+				self.pos += 1;
+			}
+
+			match (self.cur(), self.peek_char(1)) {
+				(None, _) | (Some('\n'), _) | (Some(';'), _) => break true,
+
+				// an unterminated comment goes to the end of the input, so it's the rest of the
+				// line, too. (the error is given when it's lexed for real.)
+				(Some('#'), Some('|')) => {
+					if self.skip_block_comment().is_err() {
+						break true;
+					}
+				}
+
+				_ => break false,
+			}
+		};
+
+		self.pos = saved;
+		return ret;
+	}
+}
@@ -0,0 +1,194 @@
+// Interpolation mode: strings with code inside of them, like "hello ${name}!". Lots of languages
+// have these (JavaScript's `${}`, Python's f"{}", Kotlin, Swift...).
+//
+// The tricky part is that the lexer has to switch back and forth between two completely
+// different ways of lexing:
+//
+// - in a string's *text*, everything is just characters (and escapes), up to a '"' or "${".
+// - in an *interpolation*, it's normal code, with normal tokens, up to the '}' that ends it.
+//
+// And that can nest! An interpolation can have a string in it, which can have an interpolation
+// in it, and so on:
+//
+//     "a ${f "b ${x} c"} d"
+//
+// So the lexer keeps a *stack* of modes. '"' pushes Str, "${" pushes Interp, and the thing that
+// ends each one pops it. Whatever is on top says how to lex the next token. The example above
+// turns into these tokens (the indentation shows the stack):
+//
+//     StrStart
+//         StrPart("a ")
+//         InterpStart
+//             Id(f)
+//             StrStart
+//                 StrPart("b ")
+//                 InterpStart
+//                     Id(x)
+//                 InterpEnd
+//                 StrPart(" c")
+//             StrEnd
+//         InterpEnd
+//         StrPart(" d")
+//     StrEnd
+//
+// In this mode, every string is lexed this way (even ones without any "${" in them), and "\$" is
+// an escape for a '$', in case you need a "${" that *isn't* an interpolation.
+//
+// If the source ends while something is still open, there's one error for the innermost one
+// (since that's probably the mistake), and then the lexer gives out the missing InterpEnd and
+// StrEnd tokens anyway. That way a parser can count on them always matching up, even after an
+// error.
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Mode {
+	Str(usize),    // in a string's text. the usize is where the string's '"' is.
+	Interp(usize), // in an interpolation. the usize is where its '$' is.
+}
+
+// The extra state the Lexer needs in interpolation mode.
+pub(crate) struct Interpolation {
+	// the mode stack. when it's empty, we're lexing normal code.
+	modes: Vec<Mode>,
+
+	// set once the "something wasn't closed" error has been given out at the end of the input.
+	reported_eof: bool,
+}
+
+impl Interpolation {
+	pub(crate) fn new() -> Self {
+		return Interpolation { modes: vec![], reported_eof: false };
+	}
+}
+
+impl Lexer {
+	// Makes the lexer understand "${...}" in strings (see the top of interp.rs). Like
+	// with_comments(), use it like Lexer::new(source).with_interpolation().
+	pub fn with_interpolation(mut self) -> Self {
+		self.interp = Some(Interpolation::new());
+		return self;
+	}
+
+	fn top_mode(&self) -> Option<Mode> {
+		return self.interp.as_ref().and_then(|i| i.modes.last().copied());
+	}
+
+	// True if the next token is part of a string's text.
+	pub(crate) fn in_string_text(&self) -> bool {
+		return matches!(self.top_mode(), Some(Mode::Str(_)));
+	}
+
+	// True if the next token is code in an interpolation.
+	pub(crate) fn in_interpolation(&self) -> bool {
+		return matches!(self.top_mode(), Some(Mode::Interp(_)));
+	}
+
+	// Called when lexing normal code in interpolation mode, after the trivia is skipped. This
+	// handles the tokens that change the mode: a '"' starts a string, and a '}' ends an
+	// interpolation. Returns None if the next token isn't one of those.
+	pub(crate) fn lex_mode_change(&mut self) -> Result<Option<Token>, LexError> {
+		let start = self.pos;
+
+		match (self.cur(), self.top_mode()) {
+			(None, Some(_)) => return self.lex_unterminated().map(Some),
+
+			(Some('"'), _) => {
+				self.pos += 1;
+				self.push_mode(Mode::Str(start));
+				return Ok(Some(Token::new(self.map.span(start, self.pos), TokenKind::StrStart)));
+			}
+
+			(Some('}'), Some(Mode::Interp(_))) => {
+				self.pos += 1;
+				self.pop_mode();
+				return Ok(Some(Token::new(self.map.span(start, self.pos), TokenKind::InterpEnd)));
+			}
+
+			_ => return Ok(None),
+		}
+	}
+
+	// StrPart: (StrChar | '\$')+, up to a '"' or "${".
+	// Lexes the next token in a string: a piece of text, or whatever ends the text.
+	pub(crate) fn lex_string_part(&mut self) -> Result<Token, LexError> {
+		let start = self.pos;
+
+		let mut s      = String::new();
+		let mut errors = vec![];
+
+		loop {
+			match (self.cur(), self.peek_char(1)) {
+				(Some('"'), _) | (Some('$'), Some('{')) | (None, _) => break,
+
+				(Some('\\'), _) => {
+					match self.lex_escape() {
+						Ok(c)  => s.push(c),
+						Err(e) => errors.push(e),
+					}
+				}
+
+				(Some(c), _) => {
+					s.push(c);
+					self.pos += 1;
+				}
+			}
+		}
+
+		// if there was any text, that's the token. what comes after it will be the next one.
+		if self.pos > start {
+			let token = Token::new(self.map.span(start, self.pos), TokenKind::StrPart(s));
+			return self.string_result(token, errors);
+		}
+
+		match self.cur() {
+			Some('"') => {
+				self.pos += 1;
+				self.pop_mode();
+				return Ok(Token::new(self.map.span(start, self.pos), TokenKind::StrEnd));
+			}
+
+			Some('$') => {
+				self.pos += 2; // skip the "${".
+				self.push_mode(Mode::Interp(start));
+				return Ok(Token::new(self.map.span(start, self.pos), TokenKind::InterpStart));
+			}
+
+			_ => return self.lex_unterminated(),
+		}
+	}
+
+	// At the end of the input with some strings or interpolations still open. The first time, this
+	// gives the error; after that, it gives out one InterpEnd or StrEnd for each open one.
+	fn lex_unterminated(&mut self) -> Result<Token, LexError> {
+		let end    = self.map.span(self.pos, self.pos);
+		let interp = self.interp.as_mut().expect("only called in interpolation mode");
+		let top    = *interp.modes.last().expect("only called when something is open");
+
+		if !interp.reported_eof {
+			interp.reported_eof = true;
+
+			match top {
+				Mode::Str(start)    =>
+					return Err(LexError::UnterminatedString(self.map.span(start, start + 1))),
+				Mode::Interp(start) =>
+					return Err(LexError::UnterminatedInterpolation(self.map.span(start, start + 2))),
+			}
+		}
+
+		interp.modes.pop();
+
+		match top {
+			Mode::Str(_)    => return Ok(Token::new(end, TokenKind::StrEnd)),
+			Mode::Interp(_) => return Ok(Token::new(end, TokenKind::InterpEnd)),
+		}
+	}
+
+	fn push_mode(&mut self, mode: Mode) {
+		self.interp.as_mut().unwrap().modes.push(mode);
+	}
+
+	fn pop_mode(&mut self) {
+		self.interp.as_mut().unwrap().modes.pop();
+	}
+}
@@ -0,0 +1,89 @@
+// This file turns the lexer's output into JSON, so that other programs (like a text editor
+// plugin) can use the tokens without having to parse Rust's {:?} output. There are two formats:
+//
+// - JSON: one big array of tokens, like [{...}, {...}, ...].
+// - JSON Lines: one token per line, with no array around them. This is nice for tools that
+//   want to read the tokens one at a time, or for grep.
+//
+// A token looks like this (the span's start and end are codepoint indices, just like Span):
+//
+//     {"span":{"start":1,"end":4,"line":1,"col":2},"kind":"Id","value":"add"}
+//
+// and an error looks like this:
+//
+//     {"error":"InvalidChar","message":"invalid character '$'","span":{...}}
+//
+// Most of the work is done by serde, a library that can write Rust types in many formats. The
+// #[derive(Serialize)]s on Token, TokenKind, and Span (and the impl for Symbol) tell it how.
+
+use serde::ser::{ Serialize, SerializeStruct, Serializer };
+
+use crate::*;
+
+// LexError can't use #[derive(Serialize)] like the others, since we want the error message in
+// the JSON too, and that comes from Display. So we write the object out by hand.
+impl Serialize for LexError {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut s = serializer.serialize_struct("LexError", 3)?;
+		s.serialize_field("error",   error_name(self))?;
+		s.serialize_field("message", &self.to_string())?;
+		s.serialize_field("span",    &self.span())?;
+		return s.end();
+	}
+}
+
+// The name of the variant, so tools can tell errors apart without looking at the message.
+fn error_name(e: &LexError) -> &'static str {
+	match e {
+		LexError::InvalidChar(..)          => return "InvalidChar",
+		LexError::IntOutOfRange(..)        => return "IntOutOfRange",
+		LexError::FloatOutOfRange(..)      => return "FloatOutOfRange",
+		LexError::InvalidDigit(..)         => return "InvalidDigit",
+		LexError::MissingRadixDigits(..)   => return "MissingRadixDigits",
+		LexError::MissingFraction(..)      => return "MissingFraction",
+		LexError::MissingExponent(..)      => return "MissingExponent",
+		LexError::UnterminatedString(..)   => return "UnterminatedString",
+		LexError::InvalidEscape(..)        => return "InvalidEscape",
+		LexError::InvalidUnicodeEscape(..) => return "InvalidUnicodeEscape",
+		LexError::UnterminatedComment(..)  => return "UnterminatedComment",
+		LexError::InconsistentDedent(..)   => return "InconsistentDedent",
+		LexError::MixedIndentation(..)     => return "MixedIndentation",
+		LexError::UnterminatedInterpolation(..) => return "UnterminatedInterpolation",
+	}
+}
+
+// serde_json::to_string can only fail if a type's Serialize impl fails (ours never do) or if a
+// map has non-string keys (ours don't have maps). so the expect()s below can't happen.
+
+// All the tokens as a JSON array, pretty-printed with one token per line.
+pub fn tokens_to_json(tokens: &[Token]) -> String {
+	let items = tokens.iter().map(token_to_json).collect::<Vec<_>>();
+
+	if items.is_empty() {
+		return "[]".into();
+	} else {
+		return format!("[\n\t{}\n]", items.join(",\n\t"));
+	}
+}
+
+// The tokens as JSON Lines: each token is a JSON object on its own line.
+pub fn tokens_to_json_lines(tokens: &[Token]) -> String {
+	let mut ret = String::new();
+
+	for t in tokens {
+		ret += &token_to_json(t);
+		ret += "\n";
+	}
+
+	return ret;
+}
+
+// One token as a JSON object, all on one line.
+pub fn token_to_json(token: &Token) -> String {
+	return serde_json::to_string(token).expect("tokens can always be written as JSON");
+}
+
+// One error as a JSON object, all on one line.
+pub fn error_to_json(error: &LexError) -> String {
+	return serde_json::to_string(error).expect("errors can always be written as JSON");
+}
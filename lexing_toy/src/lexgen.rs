@@ -0,0 +1,292 @@
+// A lexer *generator*. Instead of writing the lexer by hand (like Lexer in lib.rs), you describe
+// each kind of token with a regular expression, and this builds a lexer for you. This is how
+// tools like lex, flex, and Rust's logos crate work.
+//
+// It goes through the same steps you'd do on paper in class:
+//
+// 1. parse each rule's regex (regex.rs).
+// 2. build one NFA for all the rules with Thompson's construction (nfa.rs).
+// 3. turn that into a DFA with the subset construction, and minimize it (dfa.rs).
+// 4. lex by running the DFA, using its table (this file).
+//
+// The lexer follows the two usual rules for deciding which token comes next:
+//
+// - longest match: keep running the DFA as long as it can go, and use the *longest* piece of text
+//   that some rule matched. that's why "abc" is one identifier and not three.
+// - rule priority: if two rules match the same longest text, the one listed first wins. that's
+//   how a keyword rule (like "define") can beat the identifier rule.
+//
+// lisp_rules() are the rules for this crate's token grammar (see the top of lib.rs), so you can
+// compare the generated lexer with the hand-written one. They give the same tokens for every
+// valid program, with one exception: nested block comments. "Nested" means counting, and regular
+// expressions can't count! So the generated lexer only understands block comments without other
+// block comments inside them. (The hand-written lexer also finds some mistakes, like "123abc",
+// that the generated one happily lexes as two tokens, 123 and abc.)
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::*;
+
+mod charset;
+mod dfa;
+mod nfa;
+mod regex;
+mod visualize;
+
+pub use self::charset::{ Alphabet, CharSet };
+pub use self::dfa::{ Dfa, DEAD };
+pub use self::nfa::{ Nfa, NfaState };
+pub use self::regex::{ Regex, RegexError };
+
+// ------------------------------------------------------------------------------------------------
+// Rules
+// ------------------------------------------------------------------------------------------------
+
+// Turns the text that a rule matched into a TokenKind. It can fail for things that the regex
+// can't check, like a number being too big.
+pub type Convert = fn(text: &str, span: Span) -> Result<TokenKind, LexError>;
+
+pub enum Action {
+	Skip,           // throw the text away, like whitespace and comments.
+	Token(Convert), // make a token.
+}
+
+pub struct Rule {
+	pub name:    String,
+	pub pattern: String,
+	pub action:  Action,
+}
+
+impl Rule {
+	pub fn token(name: &str, pattern: &str, convert: Convert) -> Self {
+		return Rule { name: name.into(), pattern: pattern.into(), action: Action::Token(convert) };
+	}
+
+	pub fn skip(name: &str, pattern: &str) -> Self {
+		return Rule { name: name.into(), pattern: pattern.into(), action: Action::Skip };
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// TableLexer
+// ------------------------------------------------------------------------------------------------
+
+pub struct TableLexer {
+	rules:    Vec<Rule>,
+	nfa:      Nfa,
+	alphabet: Alphabet,
+	dfa:      Dfa, // minimized.
+
+	// how many states the DFA had before it was minimized, just so you can see the difference.
+	unminimized_states: usize,
+}
+
+impl TableLexer {
+	// Builds a lexer from the rules. The order of the rules matters: earlier rules win ties.
+	pub fn new(rules: Vec<Rule>) -> Result<Self, RegexError> {
+		let regexes = rules.iter()
+			.map(|r| regex::parse(&r.pattern))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let nfa = Nfa::new(&regexes);
+
+		let mut sets = vec![];
+
+		for r in &regexes {
+			r.for_each_set(&mut |set| sets.push(set));
+		}
+
+		let alphabet = Alphabet::new(&sets);
+		let dfa      = Dfa::from_nfa(&nfa, &alphabet);
+		let min      = dfa.minimize();
+
+		return Ok(TableLexer {
+			rules,
+			nfa,
+			alphabet,
+			dfa: min,
+			unminimized_states: dfa.num_states(),
+		});
+	}
+
+	pub fn rules(&self) -> &[Rule] {
+		return &self.rules;
+	}
+
+	pub fn nfa(&self) -> &Nfa {
+		return &self.nfa;
+	}
+
+	pub fn alphabet(&self) -> &Alphabet {
+		return &self.alphabet;
+	}
+
+	pub fn dfa(&self) -> &Dfa {
+		return &self.dfa;
+	}
+
+	pub fn unminimized_states(&self) -> usize {
+		return self.unminimized_states;
+	}
+
+	// Runs the DFA starting at chars[pos], and returns where the longest match ends and which
+	// rule it matched, or None if no rule matches.
+	pub fn longest_match(&self, chars: &[char], pos: usize) -> Option<(usize, usize)> {
+		let mut state = self.dfa.start;
+		let mut best  = None;
+
+		for (i, &c) in chars[pos ..].iter().enumerate() {
+			state = self.dfa.step(state, self.alphabet.class(c));
+
+			// the dead state means no rule can match any more text, so we can stop looking.
+			if state == DEAD {
+				break;
+			}
+
+			// don't stop at an accepting state! a longer match might be coming. just remember it.
+			if let Some(rule) = self.dfa.accept[state] {
+				best = Some((pos + i + 1, rule));
+			}
+		}
+
+		return best;
+	}
+
+	// Lexes the whole source, like lex() in lib.rs. Stops at the first error.
+	pub fn lex(&self, source: &str) -> Result<Vec<Token>, LexError> {
+		let map   = SourceMap::new(source);
+		let chars = source.chars().collect::<Vec<_>>();
+
+		let mut ret = vec![];
+		let mut pos = 0;
+
+		while pos < chars.len() {
+			let (end, rule) = match self.longest_match(&chars, pos) {
+				Some(m) => m,
+				None    => return Err(LexError::InvalidChar(map.span(pos, pos + 1), chars[pos])),
+			};
+
+			if let Action::Token(convert) = self.rules[rule].action {
+				let text = chars[pos .. end].iter().collect::<String>();
+				let span = map.span(pos, end);
+				ret.push(Token::new(span, convert(&text, span)?));
+			}
+
+			pos = end;
+		}
+
+		ret.push(Token::new(map.span(pos, pos), TokenKind::Eof));
+		return Ok(ret);
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// The rules for this crate's tokens
+// ------------------------------------------------------------------------------------------------
+
+// these are the pieces that show up in several of the number rules.
+// DIGITS(radix): '_'* <digit in that radix> (<digit in that radix> | '_')*
+const DEC_DIGITS: &str = "[0-9_]*[0-9][0-9_]*";
+const EXPONENT:   &str = "[eE][+\\-]?[0-9_]*[0-9][0-9_]*";
+
+pub fn lisp_rules() -> Vec<Rule> {
+	let dec_int = "[0-9][0-9_]*";
+
+	return vec![
+		Rule::skip("Whitespace",   "[ \\t\\n]+"),
+		Rule::skip("LineComment",  ";[^\\n]*"),
+		Rule::skip("BlockComment", "#\\|([^|]|\\|+[^|#])*\\|+#"),
+
+		Rule::token("LParen", "\\(", |_, _| Ok(TokenKind::LParen)),
+		Rule::token("RParen", "\\)", |_, _| Ok(TokenKind::RParen)),
+		Rule::token("Quote",  "'",   |_, _| Ok(TokenKind::Quote)),
+		Rule::token("Dot",    "\\.", |_, _| Ok(TokenKind::Dot)),
+		Rule::token("Id", "[\\p{XID_Start}_]\\p{XID_Continue}*", ident),
+
+		Rule::token("HexInt", "0x_*[0-9a-fA-F][0-9a-fA-F_]*", |text, span| int_lit(text, span, 16)),
+		Rule::token("OctInt", "0o_*[0-7][0-7_]*",             |text, span| int_lit(text, span, 8)),
+		Rule::token("BinInt", "0b_*[01][01_]*",               |text, span| int_lit(text, span, 2)),
+		Rule::token("DecInt", dec_int,                        |text, span| int_lit(text, span, 10)),
+
+		Rule::token("FloatLit",
+			&format!("{d}\\.{ds}({e})?|{d}{e}", d = dec_int, ds = DEC_DIGITS, e = EXPONENT),
+			float_lit),
+
+		Rule::token("StrLit", "\"([^\"\\\\]|\\\\[nt\\\\\"]|\\\\u\\{[0-9a-fA-F]*\\})*\"", str_lit),
+	];
+}
+
+// A much smaller set of rules: just parens, identifiers, and decimal integers (and whitespace to
+// separate them). The automata for these are small enough to actually look at.
+pub fn simple_rules() -> Vec<Rule> {
+	return vec![
+		Rule::skip("Whitespace", "[ \\t\\n]+"),
+		Rule::token("LParen", "\\(", |_, _| Ok(TokenKind::LParen)),
+		Rule::token("RParen", "\\)", |_, _| Ok(TokenKind::RParen)),
+		Rule::token("Id", "[\\p{XID_Start}_]\\p{XID_Continue}*", ident),
+		Rule::token("IntLit", "[0-9]+", |text, span| int_lit(text, span, 10)),
+	];
+}
+
+// Builds the lexer for lisp_rules().
+pub fn lisp_lexer() -> TableLexer {
+	return TableLexer::new(lisp_rules()).expect("the lisp rules are all valid regexes");
+}
+
+// Names are normalized to NFC, just like in the hand-written lexer.
+fn ident(text: &str, _span: Span) -> Result<TokenKind, LexError> {
+	return Ok(TokenKind::Id(Symbol::intern(&text.nfc().collect::<String>())));
+}
+
+// The regex already made sure the digits are right, so all that's left is the prefix, the '_'s,
+// and checking that the number isn't too big.
+fn int_lit(text: &str, span: Span, radix: u32) -> Result<TokenKind, LexError> {
+	let digits = if radix == 10 { text } else { &text[2 ..] };
+	let digits = digits.replace('_', "");
+
+	match i64::from_str_radix(&digits, radix) {
+		Ok(value) => return Ok(TokenKind::IntLit(value)),
+		Err(..)   => return Err(LexError::IntOutOfRange(span, text.into())),
+	}
+}
+
+fn float_lit(text: &str, span: Span) -> Result<TokenKind, LexError> {
+	match text.replace('_', "").parse::<f64>() {
+		Ok(value) if value.is_finite() => return Ok(TokenKind::FloatLit(value)),
+		_ => return Err(LexError::FloatOutOfRange(span, text.into())),
+	}
+}
+
+// The regex made sure every escape is one of the right kinds, but a \u{...} escape could still
+// be too long or not a real codepoint. (the error's span is the whole string, since we don't
+// know where the escape is in the source, only in the text.)
+fn str_lit(text: &str, span: Span) -> Result<TokenKind, LexError> {
+	let inside    = &text[1 .. text.len() - 1];
+	let mut chars = inside.chars();
+	let mut ret   = String::new();
+
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			ret.push(c);
+			continue;
+		}
+
+		match chars.next() {
+			Some('n') => ret.push('\n'),
+			Some('t') => ret.push('\t'),
+			Some('u') => {
+				let hex = chars.by_ref().skip(1).take_while(|&c| c != '}').collect::<String>();
+
+				match u32::from_str_radix(&hex, 16).ok().filter(|_| hex.len() <= 6).and_then(char::from_u32) {
+					Some(c) => ret.push(c),
+					None    => return Err(LexError::InvalidUnicodeEscape(span)),
+				}
+			}
+
+			Some(c) => ret.push(c), // '\\' or '"'.
+			None    => unreachable!("the regex doesn't allow a '\\' at the end"),
+		}
+	}
+
+	return Ok(TokenKind::StrLit(ret));
+}
@@ -0,0 +1,231 @@
+// Sets of characters, and the Alphabet that the DFA's table is indexed by.
+//
+// A regex like [a-z] is a *set* of characters. Unicode has over a million codepoints, so we can't
+// store sets as a big list of chars, or make a DFA table with a million columns. Instead:
+//
+// - a CharSet is a list of *ranges* of codepoints, like [('a', 'z'), ('A', 'Z')].
+// - the Alphabet splits all of Unicode into "classes": groups of characters that every CharSet in
+//   the lexer treats the same way. for the lisp lexer, 'a' and 'b' are always in the same sets, so
+//   they're the same class, and the DFA only needs one column for both of them. there are only a
+//   couple dozen classes, instead of a million characters!
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// the biggest codepoint.
+const MAX: u32 = 0x10FFFF;
+
+// ------------------------------------------------------------------------------------------------
+// CharSet
+// ------------------------------------------------------------------------------------------------
+
+// The ranges are inclusive, sorted, and never overlap or touch each other.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CharSet {
+	ranges: Vec<(u32, u32)>,
+}
+
+impl CharSet {
+	pub fn empty() -> Self {
+		return CharSet { ranges: vec![] };
+	}
+
+	pub fn single(c: char) -> Self {
+		return CharSet::range(c, c);
+	}
+
+	pub fn range(lo: char, hi: char) -> Self {
+		return CharSet::from_ranges(vec![(lo as u32, hi as u32)]);
+	}
+
+	// Every character.
+	pub fn any() -> Self {
+		return CharSet { ranges: vec![(0, MAX)] };
+	}
+
+	// Every character that char::is_alphabetic() says yes to. There's no simple formula for
+	// this, so we just try every codepoint! That takes a few milliseconds, so it's only done once.
+	pub fn alphabetic() -> Self {
+		static ALPHABETIC: LazyLock<CharSet> = LazyLock::new(|| CharSet::from_fn(char::is_alphabetic));
+		return ALPHABETIC.clone();
+	}
+
+	// The characters that can start an identifier, and that can continue one.
+	pub fn xid_start() -> Self {
+		static XID_START: LazyLock<CharSet> = LazyLock::new(|| CharSet::from_fn(unicode_ident::is_xid_start));
+		return XID_START.clone();
+	}
+
+	pub fn xid_continue() -> Self {
+		static XID_CONTINUE: LazyLock<CharSet> =
+			LazyLock::new(|| CharSet::from_fn(unicode_ident::is_xid_continue));
+		return XID_CONTINUE.clone();
+	}
+
+	// All the characters that f says yes to.
+	fn from_fn(f: fn(char) -> bool) -> Self {
+		let mut ranges: Vec<(u32, u32)> = vec![];
+
+		for c in (0 ..= MAX).filter_map(char::from_u32).filter(|&c| f(c)) {
+			match ranges.last_mut() {
+				Some((_, hi)) if *hi + 1 == c as u32 => *hi = c as u32,
+				_                                    => ranges.push((c as u32, c as u32)),
+			}
+		}
+
+		return CharSet { ranges };
+	}
+
+	// Sorts and merges the ranges so they follow the rules above.
+	fn from_ranges(mut ranges: Vec<(u32, u32)>) -> Self {
+		ranges.sort();
+		let mut ret: Vec<(u32, u32)> = vec![];
+
+		for (lo, hi) in ranges {
+			match ret.last_mut() {
+				Some((_, last_hi)) if lo <= *last_hi + 1 => *last_hi = (*last_hi).max(hi),
+				_                                        => ret.push((lo, hi)),
+			}
+		}
+
+		return CharSet { ranges: ret };
+	}
+
+	pub fn union(&self, other: &CharSet) -> Self {
+		return CharSet::from_ranges(self.ranges.iter().chain(&other.ranges).copied().collect());
+	}
+
+	// All the characters that are *not* in this set, like [^abc].
+	pub fn complement(&self) -> Self {
+		let mut ret  = vec![];
+		let mut next = 0; // the first character that might not be in the set.
+
+		for &(lo, hi) in &self.ranges {
+			if lo > next {
+				ret.push((next, lo - 1));
+			}
+
+			next = hi + 1;
+		}
+
+		if next <= MAX {
+			ret.push((next, MAX));
+		}
+
+		return CharSet { ranges: ret };
+	}
+
+	pub fn contains(&self, c: u32) -> bool {
+		// the ranges are sorted, so we can binary search. partition_point finds the first range
+		// that ends at or after c; c is in the set if that range starts at or before c.
+		let i = self.ranges.partition_point(|&(_, hi)| hi < c);
+		return i < self.ranges.len() && self.ranges[i].0 <= c;
+	}
+
+	pub fn ranges(&self) -> &[(u32, u32)] {
+		return &self.ranges;
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Alphabet
+// ------------------------------------------------------------------------------------------------
+
+pub struct Alphabet {
+	// all of Unicode is chopped into intervals at these points. interval i is the codepoints
+	// from cuts[i] up to (but not including) cuts[i + 1], and the last one goes up to MAX.
+	cuts: Vec<u32>,
+
+	// the class of each interval. lots of intervals can share the same class.
+	class_of: Vec<usize>,
+
+	// how many classes there are.
+	num_classes: usize,
+
+	// for each class, the ranges of characters in it (used for showing the class to people).
+	members: Vec<CharSet>,
+
+	// most source code is ASCII, so the classes of the ASCII characters are kept in a plain
+	// array. that's faster than binary searching through the cuts.
+	ascii: [usize; 128],
+}
+
+impl Alphabet {
+	// Makes the smallest Alphabet where every one of the sets is made of whole classes.
+	pub fn new(sets: &[&CharSet]) -> Self {
+		// every place where some set starts or stops is a cut.
+		let mut cuts = vec![0];
+
+		for set in sets {
+			for &(lo, hi) in set.ranges() {
+				cuts.push(lo);
+
+				if hi < MAX {
+					cuts.push(hi + 1);
+				}
+			}
+		}
+
+		cuts.sort();
+		cuts.dedup();
+
+		// two intervals are in the same class if they're in exactly the same sets. the
+		// "signature" of an interval is which sets it's in.
+		let mut classes  = HashMap::new();
+		let mut class_of = vec![];
+		let mut members  = vec![];
+
+		for (i, &cut) in cuts.iter().enumerate() {
+			let signature = sets.iter().map(|set| set.contains(cut)).collect::<Vec<_>>();
+			let next      = classes.len();
+			let class     = *classes.entry(signature).or_insert(next);
+
+			if class == members.len() {
+				members.push(CharSet::empty());
+			}
+
+			let end = cuts.get(i + 1).map(|&c| c - 1).unwrap_or(MAX);
+			members[class] = members[class].union(&CharSet { ranges: vec![(cut, end)] });
+			class_of.push(class);
+		}
+
+		let mut ret = Alphabet { cuts, class_of, num_classes: classes.len(), members, ascii: [0; 128] };
+
+		for c in 0 .. 128u8 {
+			ret.ascii[c as usize] = ret.class_slow(c as char);
+		}
+
+		return ret;
+	}
+
+	pub fn num_classes(&self) -> usize {
+		return self.num_classes;
+	}
+
+	// Which class a character is in.
+	pub fn class(&self, c: char) -> usize {
+		if c.is_ascii() {
+			return self.ascii[c as usize];
+		} else {
+			return self.class_slow(c);
+		}
+	}
+
+	fn class_slow(&self, c: char) -> usize {
+		// the last cut that's <= c is the interval that c is in.
+		let interval = self.cuts.partition_point(|&cut| cut <= c as u32) - 1;
+		return self.class_of[interval];
+	}
+
+	// Which classes make up a set. (the set must have been one of the ones given to new().)
+	pub fn classes_of(&self, set: &CharSet) -> Vec<usize> {
+		return (0 .. self.num_classes)
+			.filter(|&class| set.contains(self.members[class].ranges()[0].0))
+			.collect();
+	}
+
+	// All the characters in a class.
+	pub fn members(&self, class: usize) -> &CharSet {
+		return &self.members[class];
+	}
+}
@@ -0,0 +1,179 @@
+// Turning the NFA into a DFA (deterministic finite automaton) with the subset construction, and
+// then making the DFA as small as possible.
+//
+// An NFA can be in a *set* of states at once. The subset construction makes one DFA state for
+// every set of NFA states that the NFA could actually be in. Then the DFA is only ever in one
+// state, and each step is just looking up table[state][class of the next character]. Fast!
+//
+// The DFA that comes out of that usually has some states that do exactly the same thing.
+// Minimizing finds those and merges them together.
+
+use std::collections::HashMap;
+
+use super::charset::Alphabet;
+use super::nfa::Nfa;
+
+// State 0 is always the "dead" state: once you're in it, you can never accept anything, so the
+// lexer knows it can stop looking.
+pub const DEAD: usize = 0;
+
+#[derive(Debug, Clone)]
+pub struct Dfa {
+	pub num_classes: usize,
+	pub start:       usize,
+
+	// table[state * num_classes + class] is where to go from state on a character in class.
+	pub table: Vec<usize>,
+
+	// if a state accepts, which rule it accepts. if the NFA states in it accept more than one
+	// rule, the one that came first wins. that's "rule priority": it's how a keyword rule like
+	// "if" can beat the identifier rule, even though they both match "if".
+	pub accept: Vec<Option<usize>>,
+}
+
+impl Dfa {
+	// The subset construction.
+	pub fn from_nfa(nfa: &Nfa, alphabet: &Alphabet) -> Self {
+		let num_classes = alphabet.num_classes();
+
+		// in_set[class][set] says whether the characters in class are in nfa.sets[set].
+		let mut in_set = vec![vec![false; nfa.sets.len()]; num_classes];
+
+		for (i, set) in nfa.sets.iter().enumerate() {
+			for class in alphabet.classes_of(set) {
+				in_set[class][i] = true;
+			}
+		}
+
+		// each DFA state is a set of NFA states. this map goes from the set to the DFA state.
+		// the dead state is the empty set.
+		let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+		let mut sets   = vec![vec![]];
+		let mut table  = vec![];
+		let mut accept = vec![];
+		ids.insert(vec![], DEAD);
+
+		let start = nfa.closure(&[nfa.start]);
+		ids.insert(start.clone(), 1);
+		sets.push(start);
+
+		// every new DFA state gets added to sets, and we fill in its row of the table in order.
+		// so this loop keeps going until there are no more new states.
+		let mut next = 0;
+
+		while next < sets.len() {
+			let current = sets[next].clone();
+			next += 1;
+
+			accept.push(current.iter().filter_map(|&s| nfa.states[s].accept).min());
+
+			// one row for each class, in order. where can the NFA go from any of these states, on
+			// a character in that class?
+			for row in &in_set {
+				let mut targets = vec![];
+
+				for &s in &current {
+					for &(set, t) in &nfa.states[s].trans {
+						if row[set] {
+							targets.push(t);
+						}
+					}
+				}
+
+				let target = nfa.closure(&targets);
+
+				let id = match ids.get(&target) {
+					Some(&id) => id,
+					None      => {
+						let id = sets.len();
+						ids.insert(target.clone(), id);
+						sets.push(target);
+						id
+					}
+				};
+
+				table.push(id);
+			}
+		}
+
+		return Dfa { num_classes, start: 1, table, accept };
+	}
+
+	pub fn num_states(&self) -> usize {
+		return self.accept.len();
+	}
+
+	// Where to go from state on a character in class.
+	pub fn step(&self, state: usize, class: usize) -> usize {
+		return self.table[state * self.num_classes + class];
+	}
+
+	// Makes the smallest DFA that does the same thing as this one, using Moore's algorithm.
+	//
+	// We start by guessing that all states that accept the same rule (or don't accept anything)
+	// are the same. Then we keep splitting up groups: if two states in a group go to *different*
+	// groups on some character, they can't be the same, so they get split. When nothing splits
+	// anymore, each group becomes one state.
+	pub fn minimize(&self) -> Dfa {
+		let n = self.num_states();
+
+		// group[s] is which group state s is in. first, group by what they accept.
+		let mut group = renumber(&self.accept);
+		let mut num_groups = count(&group);
+
+		loop {
+			// a state's "signature" is its group, plus the groups it goes to on each class.
+			// states with the same signature stay together.
+			let signatures = (0 .. n).map(|s| {
+				let mut sig = vec![group[s]];
+				sig.extend((0 .. self.num_classes).map(|c| group[self.step(s, c)]));
+				sig
+			}).collect::<Vec<_>>();
+
+			let new_group = renumber(&signatures);
+			let new_num   = count(&new_group);
+			group = new_group;
+
+			if new_num == num_groups {
+				break;
+			}
+
+			num_groups = new_num;
+		}
+
+		// renumber() numbers the groups in the order they show up, and the dead state is state 0,
+		// so its group is group 0. that means the dead state is still state 0 in the new DFA.
+		assert_eq!(group[DEAD], DEAD);
+
+		// build the new table using one state from each group as an example.
+		let mut example = vec![DEAD; num_groups];
+
+		for s in (0 .. n).rev() {
+			example[group[s]] = s;
+		}
+
+		let mut table  = vec![];
+		let mut accept = vec![];
+
+		for &s in &example {
+			accept.push(self.accept[s]);
+			table.extend((0 .. self.num_classes).map(|c| group[self.step(s, c)]));
+		}
+
+		return Dfa { num_classes: self.num_classes, start: group[self.start], table, accept };
+	}
+}
+
+// Gives each different value a number, in the order they first show up.
+fn renumber<T: Eq + std::hash::Hash + Clone>(values: &[T]) -> Vec<usize> {
+	let mut ids = HashMap::new();
+
+	return values.iter().map(|v| {
+		let next = ids.len();
+		*ids.entry(v.clone()).or_insert(next)
+	}).collect();
+}
+
+fn count(group: &[usize]) -> usize {
+	return group.iter().max().map(|&m| m + 1).unwrap_or(0);
+}
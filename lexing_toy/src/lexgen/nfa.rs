@@ -0,0 +1,152 @@
+// Turning regexes into an NFA (nondeterministic finite automaton) with Thompson's construction.
+//
+// The idea is that every kind of regex becomes a little "fragment" of the NFA with one start state
+// and one end state, and bigger regexes are made by wiring smaller fragments together with
+// epsilon transitions (transitions that don't use up a character):
+//
+//     a       (s) --a--> (e)
+//     AB      (A's fragment) --ε--> (B's fragment)
+//     A|B     (s) --ε--> (A's fragment) --ε--> (e)
+//              \--ε--> (B's fragment) --ε--/
+//     A*      (s) --ε--> (A's fragment) --ε--> (e), plus ε from A's end back to A's start,
+//                                               and ε from s right to e (to match nothing)
+//
+// An NFA can be in many states at once, which makes it easy to build, but slow to run. That's
+// what the DFA is for.
+
+use super::charset::CharSet;
+use super::regex::Regex;
+
+#[derive(Debug, Default)]
+pub struct NfaState {
+	// where we can go without using up a character.
+	pub eps: Vec<usize>,
+
+	// (set, state): if the next character is in sets[set], we can go to state.
+	pub trans: Vec<(usize, usize)>,
+
+	// if this is the end of a rule, which rule it is.
+	pub accept: Option<usize>,
+}
+
+pub struct Nfa {
+	pub states: Vec<NfaState>,
+	pub start:  usize,
+
+	// all the different CharSets used in transitions. transitions refer to them by index, so
+	// that the same set (like [0-9], which shows up a lot) isn't stored over and over.
+	pub sets: Vec<CharSet>,
+}
+
+impl Nfa {
+	// Builds one NFA that matches any of the rules. Its start state has an ε to each rule's
+	// fragment, and the end of rule i's fragment has accept = Some(i).
+	pub fn new(rules: &[Regex]) -> Self {
+		let mut nfa = Nfa { states: vec![], start: 0, sets: vec![] };
+		nfa.start = nfa.add_state();
+
+		for (i, rule) in rules.iter().enumerate() {
+			let (s, e) = nfa.fragment(rule);
+			nfa.states[nfa.start].eps.push(s);
+			nfa.states[e].accept = Some(i);
+		}
+
+		return nfa;
+	}
+
+	fn add_state(&mut self) -> usize {
+		self.states.push(NfaState::default());
+		return self.states.len() - 1;
+	}
+
+	fn set_index(&mut self, set: &CharSet) -> usize {
+		match self.sets.iter().position(|s| s == set) {
+			Some(i) => return i,
+			None    => {
+				self.sets.push(set.clone());
+				return self.sets.len() - 1;
+			}
+		}
+	}
+
+	// Makes the fragment for a regex and returns its (start, end) states.
+	fn fragment(&mut self, regex: &Regex) -> (usize, usize) {
+		let s = self.add_state();
+		let e;
+
+		match regex {
+			Regex::Empty => {
+				e = self.add_state();
+				self.states[s].eps.push(e);
+			}
+
+			Regex::Set(set) => {
+				e = self.add_state();
+				let set = self.set_index(set);
+				self.states[s].trans.push((set, e));
+			}
+
+			Regex::Concat(parts) => {
+				// chain them together: s -> part 1 -> part 2 -> ... -> e.
+				let mut last = s;
+
+				for part in parts {
+					let (ps, pe) = self.fragment(part);
+					self.states[last].eps.push(ps);
+					last = pe;
+				}
+
+				e = last;
+			}
+
+			Regex::Alt(alts) => {
+				e = self.add_state();
+
+				for alt in alts {
+					let (as_, ae) = self.fragment(alt);
+					self.states[s].eps.push(as_);
+					self.states[ae].eps.push(e);
+				}
+			}
+
+			Regex::Star(r) | Regex::Plus(r) | Regex::Optional(r) => {
+				e = self.add_state();
+				let (rs, re) = self.fragment(r);
+
+				self.states[s].eps.push(rs);
+				self.states[re].eps.push(e);
+
+				// * and + can go around again.
+				if let Regex::Star(..) | Regex::Plus(..) = regex {
+					self.states[re].eps.push(rs);
+				}
+
+				// * and ? can skip it entirely.
+				if let Regex::Star(..) | Regex::Optional(..) = regex {
+					self.states[s].eps.push(e);
+				}
+			}
+		}
+
+		return (s, e);
+	}
+
+	// The "ε-closure" of some states: all the states you can get to from them by following only
+	// ε transitions (including the states themselves). The result is sorted.
+	pub fn closure(&self, states: &[usize]) -> Vec<usize> {
+		let mut seen  = vec![false; self.states.len()];
+		let mut stack = states.to_vec();
+		let mut ret   = vec![];
+
+		while let Some(s) = stack.pop() {
+			if !seen[s] {
+				seen[s] = true;
+				ret.push(s);
+				stack.extend(&self.states[s].eps);
+			}
+		}
+
+		ret.sort();
+		return ret;
+	}
+}
@@ -0,0 +1,288 @@
+// Parsing regular expressions. This is a little recursive descent parser, just like the ones in
+// parsing_lisp and parsing_math, but its "tokens" are single characters.
+//
+// Regex grammar:
+//
+// Alt:    Concat ('|' Concat)*
+// Concat: Repeat*
+// Repeat: Atom ('*' | '+' | '?')*
+// Atom:   '(' Alt ')' | '[' '^'? ClassItem+ ']' | '.' | Escape | <any other char>
+// ClassItem: ClassChar ('-' ClassChar)? | Property
+// Escape: '\n' | '\t' | '\r' | '\d' | Property | '\' <any punctuation>
+// Property: '\p{Alphabetic}' | '\p{XID_Start}' | '\p{XID_Continue}'
+//
+// '.' means any character except '\n', and \d means [0-9]. \p{Alphabetic} means any character
+// that char::is_alphabetic() says yes to, and \p{XID_Start} and \p{XID_Continue} are the
+// characters that can start and continue identifiers (see is_ident_start() in lib.rs).
+
+use std::fmt::{ Display, Formatter, Result as FmtResult };
+
+use super::charset::CharSet;
+
+// ------------------------------------------------------------------------------------------------
+// Regex AST
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub enum Regex {
+	Empty,                 // matches the empty string, like "()" or one side of "a|".
+	Set(CharSet),          // matches one character from the set.
+	Concat(Vec<Regex>),    // matches each one in order.
+	Alt(Vec<Regex>),       // matches any one of them.
+	Star(Box<Regex>),      // zero or more.
+	Plus(Box<Regex>),      // one or more.
+	Optional(Box<Regex>),  // zero or one.
+}
+
+impl Regex {
+	// Calls f on every CharSet in this regex. The Alphabet needs to see all of them.
+	pub fn for_each_set<'a>(&'a self, f: &mut impl FnMut(&'a CharSet)) {
+		match self {
+			Regex::Empty       => {}
+			Regex::Set(set)    => f(set),
+			Regex::Concat(rs)  |
+			Regex::Alt(rs)     => rs.iter().for_each(|r| r.for_each_set(f)),
+			Regex::Star(r)     |
+			Regex::Plus(r)     |
+			Regex::Optional(r) => r.for_each_set(f),
+		}
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// RegexError
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct RegexError {
+	pub pattern: String,
+	pub pos:     usize, // the codepoint index in the pattern where the problem is.
+	pub message: String,
+}
+
+impl Display for RegexError {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "in regex /{}/ at position {}: {}", self.pattern, self.pos, self.message)
+	}
+}
+
+impl std::error::Error for RegexError {}
+
+// ------------------------------------------------------------------------------------------------
+// Parser
+// ------------------------------------------------------------------------------------------------
+
+pub fn parse(pattern: &str) -> Result<Regex, RegexError> {
+	let mut p = Parser { pattern, chars: pattern.chars().collect(), pos: 0 };
+	let ret = p.parse_alt()?;
+
+	// parse_alt() stops at a ')' it doesn't know what to do with.
+	if p.pos < p.chars.len() {
+		return Err(p.error("unmatched ')'"));
+	}
+
+	return Ok(ret);
+}
+
+struct Parser<'p> {
+	pattern: &'p str,
+	chars:   Vec<char>,
+	pos:     usize,
+}
+
+impl<'p> Parser<'p> {
+	fn cur(&self) -> Option<char> {
+		return self.chars.get(self.pos).copied();
+	}
+
+	fn error(&self, message: &str) -> RegexError {
+		return RegexError { pattern: self.pattern.into(), pos: self.pos, message: message.into() };
+	}
+
+	// Alt: Concat ('|' Concat)*
+	fn parse_alt(&mut self) -> Result<Regex, RegexError> {
+		let mut alts = vec![self.parse_concat()?];
+
+		while self.cur() == Some('|') {
+			self.pos += 1;
+			alts.push(self.parse_concat()?);
+		}
+
+		if alts.len() == 1 {
+			return Ok(alts.pop().unwrap());
+		} else {
+			return Ok(Regex::Alt(alts));
+		}
+	}
+
+	// Concat: Repeat*
+	fn parse_concat(&mut self) -> Result<Regex, RegexError> {
+		let mut parts = vec![];
+
+		// a Concat ends at a '|' or ')' (or the end of the pattern).
+		while let Some(c) = self.cur() {
+			if c == '|' || c == ')' {
+				break;
+			}
+
+			parts.push(self.parse_repeat()?);
+		}
+
+		match parts.len() {
+			0 => return Ok(Regex::Empty),
+			1 => return Ok(parts.pop().unwrap()),
+			_ => return Ok(Regex::Concat(parts)),
+		}
+	}
+
+	// Repeat: Atom ('*' | '+' | '?')*
+	fn parse_repeat(&mut self) -> Result<Regex, RegexError> {
+		let mut ret = self.parse_atom()?;
+
+		loop {
+			ret = match self.cur() {
+				Some('*') => Regex::Star(Box::new(ret)),
+				Some('+') => Regex::Plus(Box::new(ret)),
+				Some('?') => Regex::Optional(Box::new(ret)),
+				_         => return Ok(ret),
+			};
+
+			self.pos += 1;
+		}
+	}
+
+	// Atom: '(' Alt ')' | '[' '^'? ClassItem+ ']' | '.' | Escape | <any other char>
+	fn parse_atom(&mut self) -> Result<Regex, RegexError> {
+		let c = self.cur().expect("parse_concat checks for the end");
+		self.pos += 1;
+
+		match c {
+			'(' => {
+				let ret = self.parse_alt()?;
+
+				if self.cur() != Some(')') {
+					return Err(self.error("expected ')'"));
+				}
+
+				self.pos += 1;
+				return Ok(ret);
+			}
+
+			'['             => return Ok(Regex::Set(self.parse_class()?)),
+			'.'             => return Ok(Regex::Set(CharSet::single('\n').complement())),
+			'\\'            => return Ok(Regex::Set(self.parse_escape()?)),
+			'*' | '+' | '?' => {
+				self.pos -= 1;
+				return Err(self.error(&format!("'{}' has nothing before it to repeat", c)));
+			}
+			_               => return Ok(Regex::Set(CharSet::single(c))),
+		}
+	}
+
+	// '[' '^'? ClassItem+ ']'. the '[' has been skipped already.
+	fn parse_class(&mut self) -> Result<CharSet, RegexError> {
+		let negated = self.cur() == Some('^');
+
+		if negated {
+			self.pos += 1;
+		}
+
+		let mut ret = CharSet::empty();
+
+		loop {
+			let lo = match self.cur() {
+				Some(']') if ret.ranges().is_empty() => return Err(self.error("empty character class")),
+				Some(']') => break,
+				Some(_)   => self.parse_class_char()?,
+				None      => return Err(self.error("expected ']'")),
+			};
+
+			// a range like a-z. a '-' right before the ']' is just a '-', like in [+-].
+			let item = match (lo, self.cur(), self.chars.get(self.pos + 1)) {
+				(ClassChar::Char(lo), Some('-'), Some(&next)) if next != ']' => {
+					self.pos += 1;
+
+					match self.parse_class_char()? {
+						ClassChar::Char(hi) if hi >= lo => CharSet::range(lo, hi),
+						ClassChar::Char(_)              => return Err(self.error("backwards range")),
+						ClassChar::Set(_)               => return Err(self.error("bad range")),
+					}
+				}
+
+				(ClassChar::Char(c), ..) => CharSet::single(c),
+				(ClassChar::Set(set), ..) => set,
+			};
+
+			ret = ret.union(&item);
+		}
+
+		self.pos += 1; // skip the ']'.
+
+		if negated {
+			return Ok(ret.complement());
+		} else {
+			return Ok(ret);
+		}
+	}
+
+	fn parse_class_char(&mut self) -> Result<ClassChar, RegexError> {
+		let c = self.cur().expect("checked by the caller");
+		self.pos += 1;
+
+		if c != '\\' {
+			return Ok(ClassChar::Char(c));
+		}
+
+		// an escape that means one character (like \n) is a Char, so it can be used in a range.
+		let set = self.parse_escape()?;
+
+		match set.ranges() {
+			&[(lo, hi)] if lo == hi => return Ok(ClassChar::Char(char::from_u32(lo).unwrap())),
+			_                       => return Ok(ClassChar::Set(set)),
+		}
+	}
+
+	// Escape. the '\' has been skipped already.
+	fn parse_escape(&mut self) -> Result<CharSet, RegexError> {
+		let c = match self.cur() {
+			Some(c) => c,
+			None    => return Err(self.error("expected a character after '\\'")),
+		};
+
+		self.pos += 1;
+
+		match c {
+			'n' => return Ok(CharSet::single('\n')),
+			't' => return Ok(CharSet::single('\t')),
+			'r' => return Ok(CharSet::single('\r')),
+			'd' => return Ok(CharSet::range('0', '9')),
+			'p' => {
+				// the name goes up to the '}'.
+				let len  = self.chars[self.pos ..].iter().take_while(|&&c| c != '}').count() + 1;
+				let name = self.chars[self.pos ..].iter().take(len).collect::<String>();
+
+				let set = match name.as_str() {
+					"{Alphabetic}"   => CharSet::alphabetic(),
+					"{XID_Start}"    => CharSet::xid_start(),
+					"{XID_Continue}" => CharSet::xid_continue(),
+					_ => return Err(self.error("\\p only knows {Alphabetic}, {XID_Start}, and {XID_Continue}")),
+				};
+
+				self.pos += len;
+				return Ok(set);
+			}
+
+			c if c.is_ascii_punctuation() => return Ok(CharSet::single(c)),
+
+			_ => {
+				self.pos -= 1;
+				return Err(self.error(&format!("unknown escape '\\{}'", c)));
+			}
+		}
+	}
+}
+
+enum ClassChar {
+	Char(char),
+	Set(CharSet),
+}
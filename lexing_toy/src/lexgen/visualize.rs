@@ -0,0 +1,212 @@
+// Ways to *look at* the automata, since they're pretty hard to understand as a table of numbers.
+//
+// - nfa_dot() and dfa_dot() write the automaton in Graphviz's DOT language. Save it to a file and
+//   run "dot -Tsvg file.dot -o file.svg" (or paste it into an online Graphviz viewer) to see it.
+// - trace() runs the lexer and writes down every state transition it makes, so you can follow
+//   along with the picture.
+//
+// These only work on a TableLexer. The hand-written Lexer in lib.rs doesn't have a table of
+// states to draw: its "states" are just where it is in its code (like "in the loop in
+// lex_ident()"). But simple_rules() and lisp_rules() describe exactly the same tokens as it does,
+// so their automata are what the hand-written Lexer is doing, too. For example, the Id and IntLit
+// states in simple_rules()'s DFA are the loops in lex_ident() and lex_digits(), and the paren
+// states are the '(' and ')' cases in lex_token_inner(). (examples/lexgen.rs checks that the two
+// lexers really do give the same tokens.)
+
+use std::fmt::Write;
+
+use super::*;
+
+impl TableLexer {
+	// The NFA as a DOT graph. Accepting states are double circles, labeled with their rule.
+	pub fn nfa_dot(&self) -> String {
+		let nfa = self.nfa();
+		let mut ret = header("NFA");
+		writeln!(ret, "\tstart -> {};", nfa.start).unwrap();
+
+		for (i, state) in nfa.states.iter().enumerate() {
+			if let Some(rule) = state.accept {
+				writeln!(ret, "\t{} [shape=doublecircle, label=\"{}\\n{}\"];",
+					i, i, self.rules()[rule].name).unwrap();
+			}
+
+			for &t in &state.eps {
+				writeln!(ret, "\t{} -> {} [label=\"ε\", style=dashed];", i, t).unwrap();
+			}
+
+			for &(set, t) in &state.trans {
+				writeln!(ret, "\t{} -> {} [label=\"{}\"];", i, t, escape(&describe(&nfa.sets[set])))
+					.unwrap();
+			}
+		}
+
+		ret += "}\n";
+		return ret;
+	}
+
+	// The minimized DFA as a DOT graph. The dead state (and every transition to it) is left out,
+	// since otherwise every state would have an arrow to it and you couldn't see anything else.
+	pub fn dfa_dot(&self) -> String {
+		let dfa = self.dfa();
+		let mut ret = header("DFA");
+		writeln!(ret, "\tstart -> {};", dfa.start).unwrap();
+
+		for s in (0 .. dfa.num_states()).filter(|&s| s != DEAD) {
+			if let Some(rule) = dfa.accept[s] {
+				writeln!(ret, "\t{} [shape=doublecircle, label=\"{}\\n{}\"];",
+					s, s, self.rules()[rule].name).unwrap();
+			}
+
+			// there's one transition per class, but it's much easier to read if all the classes
+			// that go to the same state are drawn as one arrow.
+			let mut targets: Vec<(usize, CharSet)> = vec![];
+
+			for class in 0 .. dfa.num_classes {
+				let t = dfa.step(s, class);
+
+				if t == DEAD {
+					continue;
+				}
+
+				let members = self.alphabet().members(class);
+
+				match targets.iter_mut().find(|(target, _)| *target == t) {
+					Some((_, set)) => *set = set.union(members),
+					None           => targets.push((t, members.clone())),
+				}
+			}
+
+			for (t, set) in targets {
+				writeln!(ret, "\t{} -> {} [label=\"{}\"];", s, t, escape(&describe(&set))).unwrap();
+			}
+		}
+
+		ret += "}\n";
+		return ret;
+	}
+
+	// Lexes the source, writing down each step the DFA takes. It looks like this:
+	//
+	//     token at 1:1:
+	//         '(' : 1 -> 3 (accepts LParen)
+	//         'a' : 3 -> dead
+	//         longest match: "(" is LParen
+	//
+	// Notice that the DFA always goes one character *past* the token: that's how it knows that
+	// the token can't get any longer.
+	pub fn trace(&self, source: &str) -> String {
+		let map   = SourceMap::new(source);
+		let chars = source.chars().collect::<Vec<_>>();
+		let dfa   = self.dfa();
+
+		let mut ret = String::new();
+		let mut pos = 0;
+
+		while pos < chars.len() {
+			writeln!(ret, "token at {}:", map.span(pos, pos)).unwrap();
+
+			let mut state = dfa.start;
+			let mut best  = None;
+
+			for (i, &c) in chars[pos ..].iter().enumerate() {
+				let class = self.alphabet().class(c);
+				let next  = dfa.step(state, class);
+				write!(ret, "    {:?} : {} -> ", c, state).unwrap();
+
+				if next == DEAD {
+					writeln!(ret, "dead").unwrap();
+					break;
+				}
+
+				match dfa.accept[next] {
+					Some(rule) => {
+						writeln!(ret, "{} (accepts {})", next, self.rules()[rule].name).unwrap();
+						best = Some((pos + i + 1, rule));
+					}
+
+					None => writeln!(ret, "{}", next).unwrap(),
+				}
+
+				state = next;
+			}
+
+			match best {
+				Some((end, rule)) => {
+					let text = chars[pos .. end].iter().collect::<String>();
+					let rule = &self.rules()[rule];
+
+					match rule.action {
+						Action::Skip     => writeln!(ret, "    longest match: {:?} is {} (skipped)", text, rule.name),
+						Action::Token(_) => writeln!(ret, "    longest match: {:?} is {}", text, rule.name),
+					}.unwrap();
+
+					pos = end;
+				}
+
+				None => {
+					writeln!(ret, "    no rule matches {:?}! stopping.", chars[pos]).unwrap();
+					return ret;
+				}
+			}
+		}
+
+		writeln!(ret, "end of input at {}.", map.span(pos, pos)).unwrap();
+		return ret;
+	}
+}
+
+fn header(name: &str) -> String {
+	let mut ret = format!("digraph {} {{\n", name);
+	ret += "\trankdir=LR;\n";
+	ret += "\tnode [shape=circle];\n";
+	ret += "\tstart [shape=point];\n";
+	return ret;
+}
+
+// A short description of a set of characters for an arrow's label, like "0-9" or "[^\n]".
+// Some sets (like all the alphabetic characters) have hundreds of ranges, so only the first few
+// are shown.
+fn describe(set: &CharSet) -> String {
+	// if the set is "almost everything," it's easier to say what's *not* in it.
+	let complement = set.complement();
+
+	if complement.ranges().len() < set.ranges().len() {
+		return format!("[^{}]", describe_ranges(complement.ranges()));
+	} else {
+		return describe_ranges(set.ranges());
+	}
+}
+
+fn describe_ranges(ranges: &[(u32, u32)]) -> String {
+	const SHOWN: usize = 4;
+
+	let mut parts = ranges.iter().take(SHOWN).map(|&(lo, hi)| {
+		if lo == hi {
+			show_char(lo)
+		} else {
+			format!("{}-{}", show_char(lo), show_char(hi))
+		}
+	}).collect::<Vec<_>>();
+
+	if ranges.len() > SHOWN {
+		parts.push(format!("…{} more", ranges.len() - SHOWN));
+	}
+
+	return parts.join(" ");
+}
+
+// Shows a character so that invisible ones (like '\n' and ' ') can still be seen.
+fn show_char(c: u32) -> String {
+	match char::from_u32(c) {
+		Some(' ')                  => return "' '".into(),
+		Some(c) if c.is_control()  => return c.escape_default().to_string(),
+		Some(c) if c as u32 > 0x7E => return format!("U+{:04X}", c as u32),
+		Some(c)                    => return c.to_string(),
+		None                       => return format!("U+{:04X}", c),
+	}
+}
+
+// DOT strings are in "double quotes", so quotes and backslashes in them have to be escaped.
+fn escape(s: &str) -> String {
+	return s.replace('\\', "\\\\").replace('"', "\\\"");
+}
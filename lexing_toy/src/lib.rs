@@ -1,8 +1,44 @@
 
+use std::collections::{ HashMap, HashSet, VecDeque };
 use std::fmt::{ Display, Formatter, Result as FmtResult };
 
+use diagnostics::Diagnostic;
+use unicode_normalization::{ is_nfc, UnicodeNormalization };
+use unicode_security::{ skeleton, MixedScript };
+
+// the Token and TokenKind types (and Span, SourceMap, and Symbol) are in the tokens crate, so
+// that the parser can use them too. they're re-exported, so lexing_toy::Token still works.
+pub use tokens::*;
+
+// JSON output for tools that want the tokens, like text editors.
+pub mod json;
+
+// lexing without throwing away the whitespace and comments.
+mod lossless;
+pub use crate::lossless::*;
+
+// relexing only the part of the source that changed after an edit.
+mod incremental;
+pub use crate::incremental::*;
+
+// indentation mode, where the lexer gives out Newline, Indent, and Dedent tokens.
+mod indent;
+use crate::indent::Layout;
+
+// interpolation mode, where strings can have code in them, like "hello ${name}!".
+mod interp;
+use crate::interp::Interpolation;
+
+// a lexer generator that builds a lexer from regular expressions. it has its own TableLexer, so
+// it's used like lexing_toy::lexgen::lisp_lexer().
+pub mod lexgen;
+
+// this one is *not* re-exported with "pub use", since it has its own Token, TokenKind, Lexer, and
+// lex() that would clash with the ones in this file. use it like lexing_toy::zero_copy::lex().
+pub mod zero_copy;
+
 // ------------------------------------------------------------------------------------------------
-// Token type
+// Token grammar
 // ------------------------------------------------------------------------------------------------
 
 /*
@@ -10,50 +46,108 @@ Token grammar:
 
 LParen:  '('
 RParen:  ')'
-Id:      IdStart IdCont*
-IdStart: <alphabetic> | '_'
-IdCont:  IdStart | Digit
-IntLit:  Digit+
-Token:   LParen | RParen | Id | IntLit
-
-Whitespace: ' ' | '\t' | '\n'
-Program:    (Whitespace? Token)* Whitespace? Eof
-*/
+Quote:   '\''
+Dot:     '.'                        (a '.' in a number is part of the FloatLit instead)
+Id:      IdStart IdCont*            (then normalized to NFC; see lex_ident())
+IdStart: <XID_Start> | '_'
+IdCont:  <XID_Continue>
+IntLit:  DecInt | '0x' Digits(16) | '0o' Digits(8) | '0b' Digits(2)
+DecInt:  Digit (Digit | '_')*
+Digits(radix): '_'* <digit in that radix> (<digit in that radix> | '_')*
+FloatLit: DecInt '.' DecInt Exponent? | DecInt Exponent
+Exponent: ('e' | 'E') ('+' | '-')? DecInt
+StrLit:  '"' StrChar* '"'
+StrChar: <any char except '"' or '\'> | Escape
+Escape:  '\' ('n' | 't' | '\' | '"') | '\u{' HexDigit+ '}'
+Token:   LParen | RParen | Quote | Dot | Id | IntLit | FloatLit | StrLit
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum TokenKind {
-	// Eof represents the end of the input. There will be one Eof token at the
-	// end of the lexer output.
-	Eof,
-	LParen,
-	RParen,
-	Id(String),
-	IntLit(i64),
-}
-
-#[derive(Debug, Clone)]
-pub struct Token {
-	pub loc:  usize, // the codepoint index in the source code.
-	pub kind: TokenKind,
-}
-
-impl Token {
-	// Self is a "magical type" that can be used in impl blocks, and refers to the type
-	// that the impl is attached to. Here it means "Token". It doesn't save much typing
-	// here, but if it were a big generic type with arguments, it would!
-	pub fn new(loc: usize, kind: TokenKind) -> Self {
-		return Token { loc, kind };
-	}
-}
+Whitespace:   ' ' | '\t' | '\n'
+LineComment:  ';' <any char except '\n'>*
+BlockComment: '#|' (BlockComment | <any char>)* '|#'
+Trivia:       Whitespace | LineComment | BlockComment
+Program:      (Trivia* Token)* Trivia* Eof
+*/
 
 // ------------------------------------------------------------------------------------------------
 // LexError type
 // ------------------------------------------------------------------------------------------------
 
+// Every error has a Span that says where it happened, so it can be shown to the user.
 #[derive(Debug)]
 pub enum LexError {
-	InvalidChar(char),
-	IntOutOfRange,
+	InvalidChar(Span, char),
+	IntOutOfRange(Span, String),      // the string is the text of the literal.
+	FloatOutOfRange(Span, String),    // same here.
+	InvalidDigit(Span, char, u32),    // the u32 is the radix (2, 8, 10, or 16).
+	MissingRadixDigits(Span, String), // the string is the prefix, like "0x".
+	MissingFraction(Span),            // like "1." with nothing after the '.'.
+	MissingExponent(Span),            // like "1e" or "1e+" with no digits after.
+	UnterminatedString(Span),         // the span is the opening '"'.
+	InvalidEscape(Span, char),        // the span is the whole escape sequence, starting at '\'.
+	InvalidUnicodeEscape(Span),       // same here.
+	UnterminatedComment(Span),        // the span is the '#|' that was never closed.
+	InconsistentDedent(Span),         // the span is the line's indentation.
+	MixedIndentation(Span),           // same here.
+	UnterminatedInterpolation(Span),  // the span is the "${" that was never closed.
+}
+
+impl LexError {
+	pub fn span(&self) -> Span {
+		match self {
+			LexError::InvalidChar(span, ..)        => return *span,
+			LexError::IntOutOfRange(span, ..)      => return *span,
+			LexError::FloatOutOfRange(span, ..)    => return *span,
+			LexError::InvalidDigit(span, ..)       => return *span,
+			LexError::MissingRadixDigits(span, ..) => return *span,
+			LexError::MissingFraction(span)        => return *span,
+			LexError::MissingExponent(span)        => return *span,
+			LexError::UnterminatedString(span)     => return *span,
+			LexError::InvalidEscape(span, ..)      => return *span,
+			LexError::InvalidUnicodeEscape(span)   => return *span,
+			LexError::UnterminatedComment(span)    => return *span,
+			LexError::InconsistentDedent(span)     => return *span,
+			LexError::MixedIndentation(span)       => return *span,
+			LexError::UnterminatedInterpolation(span) => return *span,
+		}
+	}
+
+	// Turns this error into a Diagnostic, which can be rendered with the offending source line
+	// and a ^^^ underline. The SourceMap is needed to turn our codepoint Span into byte offsets.
+	pub fn to_diagnostic(&self, map: &SourceMap) -> Diagnostic {
+		let ret = Diagnostic::error(&self.to_string(), map.byte_range(self.span()));
+
+		match self {
+			LexError::InvalidChar(..) =>
+				return ret.with_help("only parentheses, quotes, dots, identifiers, numbers, and strings are allowed"),
+			LexError::IntOutOfRange(..) =>
+				return ret.with_note(&format!("the biggest integer allowed is {}", i64::MAX)),
+			LexError::FloatOutOfRange(..) =>
+				return ret.with_note(&format!("the biggest float allowed is about {:e}", f64::MAX)),
+			LexError::InvalidDigit(_, _, radix) =>
+				return ret.with_note(&format!("{} literals can only use the digits {}",
+					radix_name(*radix), radix_digits(*radix))),
+			LexError::MissingRadixDigits(..) =>
+				return ret.with_help("put at least one digit after the prefix, like 0x1F"),
+			LexError::MissingFraction(..) =>
+				return ret.with_help("put at least one digit after the '.', like 1.0"),
+			LexError::MissingExponent(..) =>
+				return ret.with_help("put at least one digit after the 'e', like 1e10"),
+			LexError::UnterminatedString(..) =>
+				return ret.with_help("add a '\"' to end the string"),
+			LexError::InvalidEscape(..) =>
+				return ret.with_help(r#"the valid escapes are \n, \t, \\, \", and \u{...}"#),
+			LexError::InvalidUnicodeEscape(..) =>
+				return ret.with_help("unicode escapes look like \\u{1F600}, with 1 to 6 hex digits"),
+			LexError::UnterminatedComment(..) =>
+				return ret.with_help("add a '|#' to end the comment"),
+			LexError::InconsistentDedent(..) =>
+				return ret.with_help("indent this line the same as one of the lines before it"),
+			LexError::MixedIndentation(..) =>
+				return ret.with_help("indent with only spaces or only tabs, not both"),
+			LexError::UnterminatedInterpolation(..) =>
+				return ret.with_help("add a '}' to end the interpolation"),
+		}
+	}
 }
 
 // Display is a "trait," something like a Java interface. Display is Rust's equivalent to Java's
@@ -61,141 +155,912 @@ pub enum LexError {
 impl Display for LexError {
 	fn fmt(&self, f: &mut Formatter) -> FmtResult {
 		match self {
-			LexError::InvalidChar(c) => write!(f, "invalid character '{}'", c.escape_debug()),
-			LexError::IntOutOfRange  => write!(f, "integer out of range"),
+			LexError::InvalidChar(_, c)        => write!(f, "invalid character '{}'", c.escape_debug()),
+			LexError::IntOutOfRange(_, s)      => write!(f, "integer literal '{}' is out of range", s),
+			LexError::FloatOutOfRange(_, s)    => write!(f, "float literal '{}' is out of range", s),
+			LexError::MissingFraction(_)       => write!(f, "expected digits after the decimal point"),
+			LexError::MissingExponent(_)       => write!(f, "expected digits in the exponent"),
+			LexError::MissingRadixDigits(_, p) => write!(f, "expected digits after '{}'", p),
+			LexError::UnterminatedString(_)    => write!(f, "unterminated string literal"),
+			LexError::InvalidUnicodeEscape(_)  => write!(f, "invalid unicode escape sequence"),
+			LexError::UnterminatedComment(_)   => write!(f, "unterminated block comment"),
+			LexError::InconsistentDedent(_)    => write!(f, "unindent does not match any outer indentation level"),
+			LexError::MixedIndentation(_)      => write!(f, "indentation mixes tabs and spaces"),
+			LexError::UnterminatedInterpolation(_) => write!(f, "unterminated interpolation"),
+			LexError::InvalidDigit(_, c, radix) =>
+				write!(f, "invalid digit '{}' in {} literal", c.escape_debug(), radix_name(*radix)),
+			LexError::InvalidEscape(_, c) =>
+				write!(f, "invalid escape sequence '\\{}'", c.escape_debug()),
 		}
 	}
 }
 
+// These are used to make the error messages for number literals nicer.
+fn radix_name(radix: u32) -> &'static str {
+	match radix {
+		2  => return "binary",
+		8  => return "octal",
+		10 => return "decimal",
+		16 => return "hexadecimal",
+		_  => unreachable!("there are no literals with radix {}", radix),
+	}
+}
+
+fn radix_digits(radix: u32) -> &'static str {
+	match radix {
+		2  => return "0 and 1",
+		8  => return "0 to 7",
+		10 => return "0 to 9",
+		16 => return "0 to 9 and A to F",
+		_  => unreachable!("there are no literals with radix {}", radix),
+	}
+}
+
 // std::error::Error is another standard library trait, and says that LexError can be used to
 // report errors, basically. Empty {} because there are no required methods to implement.
 impl std::error::Error for LexError {}
 
+// ------------------------------------------------------------------------------------------------
+// LexWarning type
+// ------------------------------------------------------------------------------------------------
+
+// Warnings are for code that is *allowed*, but that is probably not what you meant.
+#[derive(Debug)]
+pub enum LexWarning {
+	MixedScript(Span, Symbol),              // a name with letters from more than one script.
+	Confusable(Span, Symbol, Span, Symbol), // a name that looks like an earlier (Span, Symbol).
+}
+
+impl LexWarning {
+	pub fn span(&self) -> Span {
+		match self {
+			LexWarning::MixedScript(span, ..) => return *span,
+			LexWarning::Confusable(span, ..)  => return *span,
+		}
+	}
+
+	pub fn to_diagnostic(&self, map: &SourceMap) -> Diagnostic {
+		let ret = Diagnostic::warning(&self.to_string(), map.byte_range(self.span()));
+
+		match self {
+			LexWarning::MixedScript(..) =>
+				return ret.with_note("some letters from different alphabets look exactly the same"),
+			LexWarning::Confusable(_, _, other_span, _) =>
+				return ret.with_note(&format!("the other name is at {}", other_span)),
+		}
+	}
+}
+
+impl Display for LexWarning {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			LexWarning::MixedScript(_, name) =>
+				write!(f, "the name '{}' mixes letters from different scripts", name.as_str().escape_debug()),
+			LexWarning::Confusable(_, name, _, other) =>
+				write!(f, "the name '{}' looks like '{}', but they are different names",
+					name.as_str().escape_debug(), other.as_str().escape_debug()),
+		}
+	}
+}
+
 // ------------------------------------------------------------------------------------------------
 // The lexer algorithm
 // ------------------------------------------------------------------------------------------------
 
 // These two functions implement the IdStart and IdCont rules in the lexer grammar.
+//
+// XID_Start and XID_Continue are the characters that Unicode recommends for identifiers, in
+// "Unicode Standard Annex #31." XID_Start is letters (in any language), and XID_Continue adds
+// digits, '_', and combining marks (like the accent in "e\u{301}"). Rust, Python, and JavaScript
+// all use these same rules for their identifiers!
 
-// IdStart: <alphabetic> | '_'
+// IdStart: <XID_Start> | '_'
 fn is_ident_start(c: char) -> bool {
-	return c.is_alphabetic() || c == '_';
+	return unicode_ident::is_xid_start(c) || c == '_';
 }
 
-// IdCont:  IdStart | Digit
-fn is_ident_cont (c: char) -> bool {
-	return is_ident_start(c) || c.is_ascii_digit();
+// IdCont:  <XID_Continue>
+fn is_ident_cont(c: char) -> bool {
+	return unicode_ident::is_xid_continue(c);
 }
 
-// Result<R, E> is how functions return errors in Rust. R is the return type if it succeeds;
-// E is the error type if it fails. So this function returns a Vec<Token> on success and
-// a LexError on failure.
-pub fn lex(source: &str) -> Result<Vec<Token>, LexError> {
-	// this turns source from a UTF-8 string into a vector of codepoints, so we can
-	// index it by codepoint index in O(1) time, at the cost of taking up more space.
-	// (Rust lets you re-declare variables of the same name. It's really a new variable.)
+// ------------------------------------------------------------------------------------------------
+// The Lexer type
+// ------------------------------------------------------------------------------------------------
 
-	// The syntax ".collect::<Vec<_>>()" is called the "turbofish" and is required here to
-	// say *what* to collect the chars into. I'm saying "I want a Vec of whatever." otherwise
-	// the compiler won't have enough information and won't know what data structure you want!
-	let source = source.chars().collect::<Vec<_>>();
+// A Lexer produces tokens one at a time, on demand. Instead of lexing the whole source up front,
+// a parser can just keep asking for the next token. It implements the Iterator trait (see below),
+// so it works with for loops and all the iterator methods like .map(), .filter(), .collect()...
+pub struct Lexer {
+	// this is the source code as a vector of codepoints, so we can index it by codepoint index in
+	// O(1) time, at the cost of taking up more space.
+	source: Vec<char>,
 
-	// our position in the source. since it's mut, we can move this forward through the source.
-	let mut pos = 0;
+	// our position in the source. we move this forward through the source as we lex.
+	pos: usize,
 
-	// the vec of tokens to be returned. since it's mut, we can push things into it.
-	let mut ret = vec![];
+	// used to turn codepoint indices into Spans with line and column numbers.
+	map: SourceMap,
 
-	// let's go! this loop implements the Program rule.
-	while pos < source.len() {
-		match source[pos] {
-			// Whitespace: ' ' | '\t' | '\n'
-			' ' | '\t' | '\n' => {
-				// ignore it and move on.
-				pos += 1;
+	// tokens (or errors) that have been lexed by peek() but not yet given out by next().
+	// a VecDeque is a double-ended queue, so we can push on the back and pop off the front.
+	peeked: VecDeque<Result<Token, LexError>>,
+
+	// set to true once we've lexed the Eof token or hit an error. after that, there's nothing
+	// more to lex, and the iterator ends.
+	done: bool,
+
+	// the codepoint index where the token currently being lexed starts.
+	start: usize,
+
+	// if true, errors don't stop the lexer. instead, they are recorded in `errors`, the bad
+	// characters are skipped, and an Error token is produced in their place.
+	recover: bool,
+
+	// the errors found so far in recovering mode.
+	errors: Vec<LexError>,
+
+	// if true, comments are given out as Comment tokens instead of being skipped.
+	keep_comments: bool,
+
+	// the indentation stack and friends, if we're in indentation mode. see indent.rs.
+	layout: Option<Layout>,
+
+	// the mode stack, if we're in interpolation mode. see interp.rs.
+	interp: Option<Interpolation>,
+
+	// warnings about identifiers. unlike errors, these are found in any mode, and never stop
+	// the lexer.
+	warnings: Vec<LexWarning>,
+
+	// the names check_ident() has already looked at.
+	seen: HashSet<Symbol>,
+
+	// the skeleton of each name seen so far, and the first name (and where it was) that had it.
+	skeletons: HashMap<String, (Symbol, Span)>,
+
+	// ASCII names whose skeletons haven't been figured out yet. see check_ident().
+	ascii_names: Vec<(Symbol, Span)>,
+
+	// lex_ident() puts each identifier's text in here to intern it. see there for why.
+	ident_buf: String,
+}
+
+impl Lexer {
+	pub fn new(source: &str) -> Self {
+		// The syntax ".collect::<Vec<_>>()" is called the "turbofish" and is required here to
+		// say *what* to collect the chars into. I'm saying "I want a Vec of whatever." otherwise
+		// the compiler won't have enough information and won't know what data structure you want!
+		let map    = SourceMap::new(source);
+		let source = source.chars().collect::<Vec<_>>();
+		return Lexer::from_parts(source, map);
+	}
+
+	// Makes a lexer from source code that's already been split into codepoints, and its
+	// SourceMap. Used by the incremental relexer, which keeps both of those between edits instead
+	// of making them again every time. into_parts() gives them back afterwards.
+	pub(crate) fn from_parts(source: Vec<char>, map: SourceMap) -> Self {
+		return Lexer {
+			source,
+			pos:     0,
+			map,
+			peeked:  VecDeque::new(),
+			done:    false,
+			start:   0,
+			recover: false,
+			errors:  vec![],
+			keep_comments: false,
+			layout:    None,
+			interp:    None,
+			warnings:  vec![],
+			seen:      HashSet::new(),
+			skeletons: HashMap::new(),
+			ascii_names: vec![],
+			ident_buf:   String::new(),
+		};
+	}
+
+	// Makes the lexer give out comments as Comment tokens instead of skipping them. It takes and
+	// returns self, so you can write Lexer::new(source).with_comments().
+	pub fn with_comments(mut self) -> Self {
+		self.keep_comments = true;
+		return self;
+	}
+
+	// Makes a lexer in recovering mode. It never gives out an Err; every error is recorded and
+	// can be retrieved with errors() or take_errors() once you're done with the tokens.
+	pub fn new_recovering(source: &str) -> Self {
+		let mut ret = Lexer::new(source);
+		ret.recover = true;
+		return ret;
+	}
+
+	pub(crate) fn into_parts(self) -> (Vec<char>, SourceMap) {
+		return (self.source, self.map);
+	}
+
+	// Makes the lexer start lexing at codepoint index pos instead of at the beginning. pos has to
+	// be somewhere a token (or trivia before it) could start, or you'll get nonsense. Used by the
+	// incremental relexer to skip over the part of the source that didn't change.
+	pub(crate) fn starting_at(mut self, pos: usize) -> Self {
+		self.pos   = pos;
+		self.start = pos;
+		return self;
+	}
+
+	// The errors recorded so far in recovering mode, in the order they were found.
+	pub fn errors(&self) -> &[LexError] {
+		return &self.errors;
+	}
+
+	// Takes the recorded errors out of the lexer, leaving it with an empty list.
+	pub fn take_errors(&mut self) -> Vec<LexError> {
+		return std::mem::take(&mut self.errors);
+	}
+
+	// The warnings found so far, in the order they were found.
+	pub fn warnings(&self) -> &[LexWarning] {
+		return &self.warnings;
+	}
+
+	// Takes the warnings out of the lexer, leaving it with an empty list.
+	pub fn take_warnings(&mut self) -> Vec<LexWarning> {
+		return std::mem::take(&mut self.warnings);
+	}
+
+	// The SourceMap for the source code being lexed, in case you need to turn a Span into
+	// something else (like a byte range).
+	pub fn source_map(&self) -> &SourceMap {
+		return &self.map;
+	}
+
+	// Looks at the next token without consuming it. Calling next() afterwards will give you the
+	// same token. Returns None if the lexer has already given out the Eof token (or an error).
+	pub fn peek(&mut self) -> Option<&Result<Token, LexError>> {
+		return self.peek_nth(0);
+	}
+
+	// Looks n tokens ahead without consuming anything. peek_nth(0) is the same as peek().
+	// This is "lookahead," and some grammars need more than one token of it.
+	pub fn peek_nth(&mut self, n: usize) -> Option<&Result<Token, LexError>> {
+		while self.peeked.len() <= n && !self.done {
+			let t = self.lex_token();
+			self.peeked.push_back(t);
+		}
+
+		return self.peeked.get(n);
+	}
+
+	// Lexes exactly one token (or error) from the source. This is the body of the old lex() loop.
+	fn lex_token(&mut self) -> Result<Token, LexError> {
+		// lex_token_inner() has lots of places where it returns, and we have to check every
+		// result to see if we're done. doing the real work in a helper makes that easy.
+		let result = if self.layout.is_some() { self.lex_layout_token() } else { self.lex_token_inner() };
+
+		match result {
+			Ok(t) => {
+				if t.kind == TokenKind::Eof {
+					self.done = true;
+				}
+
+				return Ok(t);
 			}
 
-			// LParen
-			'(' => {
-				ret.push(Token::new(pos, TokenKind::LParen));
-				pos += 1;
+			Err(e) => {
+				if self.recover {
+					// remember the error, skip the bad characters, and keep on going. (a bad
+					// indentation has already been skipped, and there's nothing else to skip. a
+					// string with a bad escape in it has already been lexed all the way to its
+					// closing '"', so skipping would eat the token after it. and in a string's
+					// text, skipping would skip right over the end of the string!)
+					let bad_indent = matches!(e,
+						LexError::InconsistentDedent(..) | LexError::MixedIndentation(..));
+					let bad_string = matches!(e,
+						LexError::InvalidEscape(..) | LexError::InvalidUnicodeEscape(..));
+
+					if !bad_indent && !bad_string && !self.in_string_text() {
+						self.skip_bad_run();
+					}
+
+					// the Error token counts as a token on the line, except for a bad indentation,
+					// which comes before the line's first token.
+					if let Some(layout) = &mut self.layout {
+						if !bad_indent {
+							layout.line_has_tokens = true;
+						}
+					}
+
+					self.errors.push(e);
+					return Ok(Token::new(self.map.span(self.start, self.pos), TokenKind::Error));
+				} else {
+					self.done = true;
+					return Err(e);
+				}
 			}
+		}
+	}
 
-			// RParen
-			')' => {
-				ret.push(Token::new(pos, TokenKind::RParen));
-				pos += 1;
+	// When recovering from an error, we skip everything up to the next whitespace or paren. This
+	// way, something like "@#$" is one error instead of three, and "123abc" doesn't turn into an
+	// error followed by an Id("abc").
+	fn skip_bad_run(&mut self) {
+		while self.pos < self.source.len() {
+			match self.source[self.pos] {
+				' ' | '\t' | '\n' | '(' | ')' | ';' => break,
+
+				// in an interpolation, these change the mode, so they can't be skipped.
+				'"' | '}' if self.in_interpolation() => break,
+
+				_ => self.pos += 1,
 			}
+		}
+	}
 
-			// This case is the "default", but it assigns the thing we matched on (source[pos])
-			// into this new variable "c", so "c" in the code below refers to the current character.
-			c => {
-				// Id: IdStart IdCont*
-				// this check implements the "IdStart" part of the grammar rule.
-				if is_ident_start(c) {
-					let start = pos;
+	// The character at the current position, or None if we're at the end of the source.
+	fn cur(&self) -> Option<char> {
+		return self.source.get(self.pos).copied();
+	}
+
+	// Id: IdStart IdCont*
+	// When this is called, the current character is an IdStart.
+	fn lex_ident(&mut self) -> Token {
+		let start = self.pos;
+
+		// a mut String variable is like a StringBuilder/StringBuffer in Java. the source is a
+		// Vec<char>, so the name has to be copied into a String to look it up. but instead of
+		// making a new String for every identifier, we reuse the same one over and over.
+		// (mem::take() borrows it out of self, so we can still call self's methods.)
+		let mut s = std::mem::take(&mut self.ident_buf);
+		s.clear();
+
+		// this loop implements the "IdCont*" part of the grammar rule.
+		while let Some(c) = self.cur() {
+			if !is_ident_cont(c) {
+				break;
+			}
+
+			// we can push characters into the string, similar to how Vecs work.
+			s.push(c);
+			self.pos += 1;
+		}
+
+		// "tést" can be written with a precomposed 'é' (U+00E9), or with an 'e' followed by a
+		// combining accent (U+0301). they look exactly the same, so they should be the same
+		// name! normalizing to NFC turns the second way into the first way. is_nfc() is a quick
+		// check, so we only make a new string for the (rare) names that need it.
+		if !is_nfc(&s) {
+			s = s.nfc().collect();
+		}
+
+		// interning gives us the same Symbol every time we see the same name. it only copies the
+		// string the first time it sees a name.
+		let span = self.map.span(start, self.pos);
+		let sym  = Symbol::intern(&s);
+		self.ident_buf = s;
+		self.check_ident(sym, span);
+		return Token::new(span, TokenKind::Id(sym));
+	}
+
+	// Some identifiers are allowed, but are probably a mistake (or someone trying to trick you),
+	// so this gives a warning about them. These checks come from "Unicode Technical Standard #39,"
+	// and the unicode-security crate does the hard parts.
+	fn check_ident(&mut self, sym: Symbol, span: Span) {
+		// each name only has to be checked the first time it shows up.
+		if !self.seen.insert(sym) {
+			return;
+		}
+
+		let name = sym.as_str();
+
+		// two ASCII names are never a problem (see below), and most programs are *all* ASCII. so
+		// an ASCII name is just put aside until the first non-ASCII name shows up. that way, an
+		// all-ASCII program never has to pay for making skeletons.
+		if name.is_ascii() {
+			if self.skeletons.is_empty() {
+				self.ascii_names.push((sym, span));
+				return;
+			}
+		} else {
+			for (other, other_span) in std::mem::take(&mut self.ascii_names) {
+				self.skeletons.entry(skeleton(other.as_str()).collect()).or_insert((other, other_span));
+			}
+		}
+
+		// mixed scripts: like "pаypal", where the 'а' is actually Cyrillic, not Latin.
+		if !name.is_single_script() {
+			self.warnings.push(LexWarning::MixedScript(span, sym));
+		}
+
+		// confusables: two different names that look the same, like "scope" (all Latin) and
+		// "ѕсоре" (all Cyrillic!). the "skeleton" of a name replaces every character with the one
+		// it looks like, so two names that look the same have the same skeleton.
+		let skel = skeleton(name).collect::<String>();
+
+		match self.skeletons.get(&skel) {
+			// in plain ASCII, the skeleton says that "rn" looks like "m" and "0" looks like "O".
+			// that's true in some fonts, but warning about it would be really annoying, so two
+			// ASCII names are never confusable.
+			Some(&(other, other_span)) => {
+				if !(name.is_ascii() && other.as_str().is_ascii()) {
+					self.warnings.push(LexWarning::Confusable(span, sym, other_span, other));
+				}
+			}
+
+			None => {
+				self.skeletons.insert(skel, (sym, span));
+			}
+		}
+	}
+
+	// StrLit: '"' StrChar* '"'
+	fn lex_string(&mut self) -> Result<Token, LexError> {
+		let start = self.pos;
+		self.pos += 1; // skip the opening '"'.
+
+		let mut s = String::new();
 
-					// a mut String variable is like a StringBuilder/StringBuffer in Java.
-					let mut s = String::new();
+		// a string can have more than one bad escape in it, so we hang on to all of them.
+		let mut errors = vec![];
 
-					// this loop implements the "IdCont*" part of the grammar rule.
-					while pos < source.len() && is_ident_cont(source[pos]) {
-						// we can push characters into the string, similar to how Vecs work.
-						s.push(source[pos]);
-						pos += 1;
+		loop {
+			match self.cur() {
+				// the string ended, yay!
+				Some('"') => {
+					self.pos += 1;
+					break;
+				}
+
+				// Escape
+				Some('\\') => {
+					match self.lex_escape() {
+						Ok(c)  => s.push(c),
+						Err(e) => errors.push(e),
 					}
+				}
 
-					ret.push(Token::new(start, TokenKind::Id(s)));
+				// StrChar. strings are allowed to have newlines in them.
+				Some(c) => {
+					s.push(c);
+					self.pos += 1;
+				}
 
-				// IntLit: Digit+
-				} else if c.is_ascii_digit() {
-					let start = pos;
+				// we ran off the end of the source before finding the closing '"'. we point at
+				// the opening '"', since that's what the user has to look at to fix it.
+				None => return Err(LexError::UnterminatedString(self.map.span(start, start + 1))),
+			}
+		}
+
+		let token = Token::new(self.map.span(start, self.pos), TokenKind::StrLit(s));
+		return self.string_result(token, errors);
+	}
+
+	// Gives back the token for a string, or one of the errors found in it.
+	fn string_result(&mut self, token: Token, mut errors: Vec<LexError>) -> Result<Token, LexError> {
+		if errors.is_empty() {
+			return Ok(token);
+		}
+
+		// only one error can be returned. if we're recovering, we record all the others right
+		// here (lex_token() will record the returned one after them, so they stay in order).
+		// if we're not recovering, the lexer stops at the first error anyway.
+		if self.recover {
+			let last = errors.pop().expect("there was at least one error");
+			self.errors.append(&mut errors);
+			return Err(last);
+		} else {
+			return Err(errors.remove(0));
+		}
+	}
+
+	// Escape: '\' ('n' | 't' | '\' | '"') | '\u{' HexDigit+ '}'
+	// (in interpolation mode, '\$' is an escape too.)
+	// When this is called, the current character is the '\'. Returns the character the escape
+	// sequence stands for.
+	fn lex_escape(&mut self) -> Result<char, LexError> {
+		let start = self.pos;
+		self.pos += 1; // skip the '\'.
+
+		let c = match self.cur() {
+			Some(c) => c,
+
+			// the source ended right after the '\'. lex_string() will see that it's at the end
+			// and report an unterminated string, which is the real problem.
+			None => return Ok('\\'),
+		};
+
+		self.pos += 1;
+
+		match c {
+			'n'  => return Ok('\n'),
+			't'  => return Ok('\t'),
+			'\\' => return Ok('\\'),
+			'"'  => return Ok('"'),
+			'u'  => return self.lex_unicode_escape(start),
+			'$' if self.interp.is_some() => return Ok('$'),
+			_    => return Err(LexError::InvalidEscape(self.map.span(start, self.pos), c)),
+		}
+	}
+
+	// '\u{' HexDigit+ '}'
+	// The "\u" has already been skipped, and start is where the '\' was.
+	fn lex_unicode_escape(&mut self, start: usize) -> Result<char, LexError> {
+		if self.cur() != Some('{') {
+			return Err(LexError::InvalidUnicodeEscape(self.map.span(start, self.pos)));
+		}
+
+		self.pos += 1;
+
+		let mut hex = String::new();
+
+		while let Some(c) = self.cur() {
+			if !c.is_ascii_hexdigit() {
+				break;
+			}
+
+			hex.push(c);
+			self.pos += 1;
+		}
+
+		if self.cur() != Some('}') {
+			return Err(LexError::InvalidUnicodeEscape(self.map.span(start, self.pos)));
+		}
+
+		self.pos += 1;
+		let span = self.map.span(start, self.pos);
+
+		// a codepoint can't be more than 6 hex digits, so this also makes sure the number fits
+		// in a u32. then char::from_u32 checks that it's a real codepoint (not too big, and not
+		// one of the "surrogate" values that only exist in UTF-16).
+		if hex.is_empty() || hex.len() > 6 {
+			return Err(LexError::InvalidUnicodeEscape(span));
+		}
+
+		let value = u32::from_str_radix(&hex, 16).expect("only hex digits were collected");
+
+		match char::from_u32(value) {
+			Some(c) => return Ok(c),
+			None    => return Err(LexError::InvalidUnicodeEscape(span)),
+		}
+	}
+
+	// The character n places after the current one, or None if that's past the end.
+	fn peek_char(&self, n: usize) -> Option<char> {
+		return self.source.get(self.pos + n).copied();
+	}
 
-					let mut num = String::new();
+	// Trivia: Whitespace | LineComment | BlockComment
+	// Skips all the trivia before the next token. If we're keeping comments, this stops at the
+	// first comment and returns it as a token instead.
+	fn skip_trivia(&mut self) -> Result<Option<Token>, LexError> {
+		loop {
+			// Whitespace: ' ' | '\t' | '\n'
+			// (in indentation mode, a '\n' can be a token instead. see indent.rs.)
+			while let Some(c @ ' ') | Some(c @ '\t') | Some(c @ '\n') = self.cur() {
+				if c == '\n' && self.newlines_matter() {
+					break;
+				}
+
+				self.pos += 1;
+			}
+
+			self.start = self.pos;
+
+			match (self.cur(), self.peek_char(1)) {
+				(Some(';'), _)         => self.skip_line_comment(),
+				(Some('#'), Some('|')) => self.skip_block_comment()?,
+				_                      => return Ok(None), // no more trivia.
+			}
+
+			if self.keep_comments {
+				let text = self.source[self.start .. self.pos].iter().collect();
+				let span = self.map.span(self.start, self.pos);
+				return Ok(Some(Token::new(span, TokenKind::Comment(text))));
+			}
+		}
+	}
+
+	// LineComment: ';' <any char except '\n'>*
+	fn skip_line_comment(&mut self) {
+		while self.cur().is_some() && self.cur() != Some('\n') {
+			self.pos += 1;
+		}
+	}
+
+	// BlockComment: '#|' (BlockComment | <any char>)* '|#'
+	// Block comments can be *nested*, like "#| outer #| inner |# still outer |#". That can't be
+	// done with a regular expression, but it's easy with a stack: push on '#|', pop on '|#'.
+	fn skip_block_comment(&mut self) -> Result<(), LexError> {
+		// where each '#|' that hasn't been closed yet starts.
+		let mut opened = vec![];
 
-					// The c.is_ascii_digit() above and this loop together implement the
-					// "Digit+" part of the grammar rule.
-					while pos < source.len() && source[pos].is_ascii_digit() {
-						num.push(source[pos]);
-						pos += 1;
+		loop {
+			match (self.cur(), self.peek_char(1)) {
+				(Some('#'), Some('|')) => {
+					opened.push(self.pos);
+					self.pos += 2;
+				}
+
+				(Some('|'), Some('#')) => {
+					opened.pop();
+					self.pos += 2;
+
+					if opened.is_empty() {
+						return Ok(());
 					}
+				}
+
+				(Some(_), _) => self.pos += 1,
+
+				// we ran out of source. point at the innermost comment that wasn't closed.
+				(None, _) => {
+					let start = *opened.last().expect("at least one comment is open");
+					return Err(LexError::UnterminatedComment(self.map.span(start, start + 2)));
+				}
+			}
+		}
+	}
+
+	// IntLit | FloatLit
+	// Number literals are the most complicated tokens so far! When this is called, the current
+	// character is a digit.
+	fn lex_number(&mut self) -> Result<Token, LexError> {
+		let start = self.pos;
+
+		// '0x' Digits(16) | '0o' Digits(8) | '0b' Digits(2)
+		// this is a lookahead of *two* characters: we have to see the '0' and the letter.
+		let radix = match (self.cur(), self.peek_char(1)) {
+			(Some('0'), Some('x')) => 16,
+			(Some('0'), Some('o')) => 8,
+			(Some('0'), Some('b')) => 2,
+			_                      => 10,
+		};
+
+		if radix != 10 {
+			self.pos += 2; // skip the prefix.
+			let digits = self.lex_digits(radix)?;
+
+			if digits.is_empty() {
+				let prefix = self.source[start .. start + 2].iter().collect();
+				return Err(LexError::MissingRadixDigits(self.map.span(start, self.pos), prefix));
+			}
+
+			// some rules, like "can't exceed the capacity of a 64-bit integer," can't
+			// be encoded in the grammar rules and have to be checked manually.
+			// from_str_radix returns Ok() if it succeeded and Err() if it failed.
+			let span = self.map.span(start, self.pos);
+
+			match i64::from_str_radix(&digits, radix) {
+				Ok(value) => return Ok(Token::new(span, TokenKind::IntLit(value))),
+				Err(..)   => return Err(LexError::IntOutOfRange(span, self.text(start))),
+			}
+		}
+
+		// DecInt
+		let mut num = self.lex_digits(10)?;
+		let mut is_float = false;
+
+		// '.' DecInt
+		if self.cur() == Some('.') {
+			is_float = true;
+			self.pos += 1;
+			let fraction = self.lex_digits(10)?;
+
+			if fraction.is_empty() {
+				return Err(LexError::MissingFraction(self.map.span(start, self.pos)));
+			}
+
+			num.push('.');
+			num.push_str(&fraction);
+		}
 
+		// Exponent: ('e' | 'E') ('+' | '-')? DecInt
+		if let Some('e') | Some('E') = self.cur() {
+			is_float = true;
+			self.pos += 1;
+			num.push('e');
+
+			if let Some(sign @ '+') | Some(sign @ '-') = self.cur() {
+				self.pos += 1;
+				num.push(sign);
+			}
+
+			let exponent = self.lex_digits(10)?;
+
+			if exponent.is_empty() {
+				return Err(LexError::MissingExponent(self.map.span(start, self.pos)));
+			}
+
+			num.push_str(&exponent);
+		}
+
+		// after the exponent, there can't be any more letters, like "1e5x".
+		if let Some(c) = self.cur() {
+			if is_ident_cont(c) {
+				return Err(LexError::InvalidChar(self.map.span(self.pos, self.pos + 1), c));
+			}
+		}
+
+		let span = self.map.span(start, self.pos);
+
+		if is_float {
+			// parse::<f64>() doesn't fail on huge numbers, it gives you infinity instead.
+			// we'd rather tell the user about it.
+			match num.parse::<f64>() {
+				Ok(value) if value.is_finite() => return Ok(Token::new(span, TokenKind::FloatLit(value))),
+				_ => return Err(LexError::FloatOutOfRange(span, self.text(start))),
+			}
+		} else {
+			match num.parse::<i64>() {
+				// this match arm declares the "value" variable, and it contains
+				// the actual integer that was returned by parse.
+				Ok(value) => return Ok(Token::new(span, TokenKind::IntLit(value))),
+				Err(..)   => return Err(LexError::IntOutOfRange(span, self.text(start))),
+			}
+		}
+	}
+
+	// Digits(radix): lexes a run of digits and '_' separators, and returns just the digits.
+	// It might return an empty string, if there weren't any digits.
+	fn lex_digits(&mut self, radix: u32) -> Result<String, LexError> {
+		let mut digits = String::new();
+
+		// we keep going on *any* letter or digit, not just the ones that are valid in this radix.
+		// that way, "0b102" gives a nice "invalid digit '2'" error instead of lexing as 0b10 and 2.
+		while let Some(c) = self.cur() {
+			if c == '_' {
+				// separators are just for humans to read, so they're skipped.
+			} else if c.is_digit(radix) {
+				digits.push(c);
+			} else if radix == 10 && (c == 'e' || c == 'E') {
+				// in decimal, 'e' starts the exponent, so lex_number() will deal with it.
+				break;
+			} else if c.is_alphanumeric() {
+				let span = self.map.span(self.pos, self.pos + 1);
+
+				if radix == 10 {
 					// this rule makes things like "123abc" invalid. this is actually
 					// a lookahead because we're just checking the next character without
 					// making it part of this token.
-					if pos < source.len() && source[pos].is_alphabetic() {
-						// If we encounter an error condition, we return an Err() value
-						// containing the error. This is sort of like throwing an exception
-						// in Java.
-						return Err(LexError::InvalidChar(source[pos]));
-					}
+					return Err(LexError::InvalidChar(span, c));
+				} else {
+					return Err(LexError::InvalidDigit(span, c, radix));
+				}
+			} else {
+				break;
+			}
 
-					// some rules, like "can't exceed the capacity of a 64-bit integer," can't
-					// be encoded in the grammar rules and have to be checked manually.
-					// this function returns Ok() if it succeeded and Err() if it failed.
-					match i64::from_str_radix(&num, 10) {
-						// this match arm declares the "value" variable, and it contains
-						// the actual integer that was returned by i64::from_str_radix.
-						Ok(value) => {
-							ret.push(Token::new(start, TokenKind::IntLit(value)));
-						}
+			self.pos += 1;
+		}
 
-						Err(..) => {
-							return Err(LexError::IntOutOfRange)
-						}
-					}
+		return Ok(digits);
+	}
+
+	// The source code from start up to the current position, as a String.
+	fn text(&self, start: usize) -> String {
+		return self.source[start .. self.pos].iter().collect();
+	}
+
+	fn lex_token_inner(&mut self) -> Result<Token, LexError> {
+		// in interpolation mode, a string's text is lexed completely differently. (there's no
+		// trivia to skip in there, either!)
+		if self.in_string_text() {
+			return self.lex_string_part();
+		}
+
+		// skip whitespace and comments. if this gives us a comment token, we're done.
+		if let Some(comment) = self.skip_trivia()? {
+			return Ok(comment);
+		}
+
+		// and in interpolation mode, some tokens change the mode.
+		if self.interp.is_some() {
+			if let Some(t) = self.lex_mode_change()? {
+				return Ok(t);
+			}
+		}
+
+		// these are just shorter names for the fields so the code below is easier to read.
+		let source = &self.source;
+		let pos = &mut self.pos;
+		let map = &self.map;
+
+		// If we get to the end of the source, put an Eof token at the end of the output.
+		if *pos == source.len() {
+			return Ok(Token::new(map.span(*pos, *pos), TokenKind::Eof));
+		}
+
+		match source[*pos] {
+			// LParen
+			'(' => {
+				*pos += 1;
+				return Ok(Token::new(map.span(*pos - 1, *pos), TokenKind::LParen));
+			}
+
+			// RParen
+			')' => {
+				*pos += 1;
+				return Ok(Token::new(map.span(*pos - 1, *pos), TokenKind::RParen));
+			}
+
+			// Quote
+			'\'' => {
+				*pos += 1;
+				return Ok(Token::new(map.span(*pos - 1, *pos), TokenKind::Quote));
+			}
+
+			// Dot
+			'.' => {
+				*pos += 1;
+				return Ok(Token::new(map.span(*pos - 1, *pos), TokenKind::Dot));
+			}
+
+			// StrLit
+			'"' => return self.lex_string(),
+
+			// This case is the "default", but it assigns the thing we matched on (source[pos])
+			// into this new variable "c", so "c" in the code below refers to the current character.
+			c => {
+				// Id: IdStart IdCont*
+				// this check implements the "IdStart" part of the grammar rule.
+				if is_ident_start(c) {
+					return Ok(self.lex_ident());
+
+				// IntLit | FloatLit
+				} else if c.is_ascii_digit() {
+					return self.lex_number();
 				} else {
 					// any other character is no good.
-					return Err(LexError::InvalidChar(c));
+					return Err(LexError::InvalidChar(map.span(*pos, *pos + 1), c));
 				}
 			}
 		}
 	}
+}
+
+// This is how we implement the Iterator trait. An Iterator only needs one method, next(), which
+// returns Some(item) for each item and then None when it's done. Every other iterator method
+// (map, filter, collect, etc.) is built on top of next(), so we get them for free!
+impl Iterator for Lexer {
+	// "type Item" says what kind of thing the iterator gives out. Each item is either a token or
+	// an error, so a parser can stop (or not) whenever it sees an error.
+	type Item = Result<Token, LexError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// if peek() already lexed some tokens, give those out first.
+		if let Some(t) = self.peeked.pop_front() {
+			return Some(t);
+		}
+
+		if self.done {
+			return None;
+		} else {
+			return Some(self.lex_token());
+		}
+	}
+}
 
-	// If we get down here, we successfully lexed the entire string,
-	// so let's put an Eof token at the end of the output.
-	ret.push(Token::new(pos, TokenKind::Eof));
+// ------------------------------------------------------------------------------------------------
+// lex()
+// ------------------------------------------------------------------------------------------------
+
+// Result<R, E> is how functions return errors in Rust. R is the return type if it succeeds;
+// E is the error type if it fails. So this function returns a Vec<Token> on success and
+// a LexError on failure.
+pub fn lex(source: &str) -> Result<Vec<Token>, LexError> {
+	// this is a neat trick: collecting an iterator of Results into a Result<Vec<_>, _> gives you
+	// Ok(all the tokens) if every item was Ok, or the first Err it finds. since the Lexer stops
+	// after the Eof token (or an error), this lexes the whole source and nothing more.
+	return Lexer::new(source).collect();
+}
 
-	// we indicate success by returning an Ok() value containing the thing we want to return.
-	return Ok(ret);
-}
\ No newline at end of file
+// This is like lex(), but it doesn't stop at the first error. Instead, it returns all the tokens
+// (with Error tokens wherever something went wrong) *and* a list of all the errors it found.
+// If the list is empty, there were no errors!
+pub fn lex_recovering(source: &str) -> (Vec<Token>, Vec<LexError>) {
+	let mut lexer = Lexer::new_recovering(source);
+
+	// .by_ref() lets us collect the tokens without giving away the lexer, since we still need
+	// to get the errors out of it afterwards.
+	let tokens = lexer.by_ref().map(|t| t.expect("recovering lexer never fails")).collect();
+	return (tokens, lexer.take_errors());
+}
@@ -0,0 +1,274 @@
+// This file has a "lossless" version of the lexer's output. Normally the lexer throws away the
+// whitespace and comments (the "trivia"), since the parser doesn't care about them. But some
+// tools *do* care: a code formatter or a refactoring tool has to change some code while leaving
+// the rest of the file exactly the way the user wrote it, comments and all.
+//
+// So here, every token holds on to the trivia around it, and gluing together all the tokens'
+// text gives you back the *exact* source code you started with, byte for byte. Even code with
+// errors in it round-trips, since the bad parts become Error tokens that hold the bad text.
+//
+// The trivia is split up the same way that Roslyn (the C# compiler) and rust-analyzer do it:
+//
+// - a token's *trailing* trivia is everything after it on the same line, up to and including the
+//   newline at the end of that line.
+// - a token's *leading* trivia is everything else before it.
+//
+// So for this code:
+//
+//     ; add some numbers
+//     (add 1 2) ; three
+//
+// the '(' token gets "; add some numbers" and "\n" as leading trivia, and the ')' token gets
+// " ", "; three", and "\n" as trailing trivia. This way, a comment at the end of a line "belongs"
+// to the code before it, and a comment on its own line belongs to the code after it.
+//
+// On top of the tokens there's a Cst ("concrete syntax tree"), which is like an AST but keeps
+// every token, including the parens and the trivia, so it can be turned back into source too.
+
+use std::iter::Peekable;
+
+use crate::*;
+
+// ------------------------------------------------------------------------------------------------
+// Trivia
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TriviaKind {
+	Whitespace,   // a run of spaces and tabs.
+	Newline,      // a single '\n'.
+	LineComment,  // from ';' up to (but not including) the '\n'.
+	BlockComment, // from '#|' to the matching '|#'.
+}
+
+#[derive(Debug, Clone)]
+pub struct Trivia {
+	pub kind: TriviaKind,
+	pub span: Span,
+	pub text: String,
+}
+
+// ------------------------------------------------------------------------------------------------
+// LosslessToken
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct LosslessToken {
+	pub leading:  Vec<Trivia>,
+	pub token:    Token,
+	pub text:     String, // the token's own source text, like "0xFF" for IntLit(255).
+	pub trailing: Vec<Trivia>,
+}
+
+impl LosslessToken {
+	pub fn kind(&self) -> &TokenKind {
+		return &self.token.kind;
+	}
+
+	// The token's text with all its trivia around it.
+	pub fn full_text(&self) -> String {
+		let mut ret = String::new();
+		self.write_to(&mut ret);
+		return ret;
+	}
+
+	fn write_to(&self, out: &mut String) {
+		for t in &self.leading {
+			*out += &t.text;
+		}
+
+		*out += &self.text;
+
+		for t in &self.trailing {
+			*out += &t.text;
+		}
+	}
+}
+
+// Lexes the source without losing anything. Like lex_recovering(), it keeps going after errors,
+// and returns all the errors too. The last token is always Eof, and any trivia at the very end
+// of the file is its leading trivia.
+pub fn lex_lossless(source: &str) -> (Vec<LosslessToken>, Vec<LexError>) {
+	let map   = SourceMap::new(source);
+	let chars = source.chars().collect::<Vec<_>>();
+
+	// first, turn the source into a flat list of pieces: trivia and (real) tokens, in order. the
+	// lexer gives us comments as tokens, and the whitespace is whatever is in between tokens.
+	let mut lexer  = Lexer::new_recovering(source).with_comments();
+	let mut pieces = vec![];
+	let mut last   = 0; // the codepoint index where the last token ended.
+
+	for t in &mut lexer {
+		let t = t.expect("recovering lexers never return Err");
+
+		whitespace_trivia(&chars, &map, last, t.span.start, &mut pieces);
+		last = t.span.end;
+
+		let text = source[map.byte_range(t.span)].to_string();
+
+		match &t.kind {
+			TokenKind::Comment(c) => {
+				let kind = if c.starts_with(';') { TriviaKind::LineComment }
+				           else                  { TriviaKind::BlockComment };
+				pieces.push(Piece::Trivia(Trivia { kind, span: t.span, text }));
+			}
+
+			_ => pieces.push(Piece::Token(t, text)),
+		}
+	}
+
+	// then, hand out the trivia to the tokens. after each token, trivia is trailing until the
+	// end of the line; after that, it's leading trivia for the next token.
+	let mut ret: Vec<LosslessToken> = vec![];
+	let mut leading     = vec![];
+	let mut in_trailing = false;
+
+	for piece in pieces {
+		match piece {
+			Piece::Token(token, text) => {
+				let leading = std::mem::take(&mut leading);
+				ret.push(LosslessToken { leading, token, text, trailing: vec![] });
+				in_trailing = true;
+			}
+
+			Piece::Trivia(trivia) if in_trailing => {
+				in_trailing = trivia.kind != TriviaKind::Newline;
+				ret.last_mut().expect("there's a token before this").trailing.push(trivia);
+			}
+
+			Piece::Trivia(trivia) => leading.push(trivia),
+		}
+	}
+
+	assert!(leading.is_empty(), "the Eof token should have been last");
+	return (ret, lexer.take_errors());
+}
+
+// Turns a list of lossless tokens back into source code. If the tokens came from lex_lossless(),
+// this gives you back exactly the same string.
+pub fn to_source(tokens: &[LosslessToken]) -> String {
+	let mut ret = String::new();
+
+	for t in tokens {
+		t.write_to(&mut ret);
+	}
+
+	return ret;
+}
+
+enum Piece {
+	Trivia(Trivia),
+	Token(Token, String),
+}
+
+// Splits the whitespace between codepoints start and end into Whitespace and Newline trivia.
+fn whitespace_trivia(chars: &[char], map: &SourceMap, start: usize, end: usize, out: &mut Vec<Piece>) {
+	let mut i = start;
+
+	while i < end {
+		let run_start = i;
+
+		let kind = if chars[i] == '\n' {
+			i += 1;
+			TriviaKind::Newline
+		} else {
+			while i < end && chars[i] != '\n' {
+				i += 1;
+			}
+
+			TriviaKind::Whitespace
+		};
+
+		let span = map.span(run_start, i);
+		let text = chars[run_start .. i].iter().collect();
+		out.push(Piece::Trivia(Trivia { kind, span, text }));
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Cst
+// ------------------------------------------------------------------------------------------------
+
+// A concrete syntax tree. Unlike parsing_lisp's Exp, it keeps the parens as tokens, and it never
+// fails: a missing ')' just means close is None, and an extra ')' is left as an Atom.
+#[derive(Debug, Clone)]
+pub enum Cst {
+	Atom(LosslessToken),
+
+	List {
+		open:  LosslessToken,
+		items: Vec<Cst>,
+		close: Option<LosslessToken>,
+	},
+}
+
+impl Cst {
+	// Builds the CSTs for a whole file. The Eof token is returned too, since it holds the trivia
+	// at the end of the file.
+	pub fn parse(tokens: Vec<LosslessToken>) -> (Vec<Cst>, LosslessToken) {
+		let mut tokens = tokens.into_iter().peekable();
+		let mut ret    = vec![];
+
+		while !Cst::at_eof(&mut tokens) {
+			ret.push(Cst::parse_one(&mut tokens));
+		}
+
+		return (ret, tokens.next().expect("lex_lossless always ends with Eof"));
+	}
+
+	fn parse_one(tokens: &mut Peekable<impl Iterator<Item = LosslessToken>>) -> Cst {
+		let t = tokens.next().expect("parse_one is never called at Eof");
+
+		if *t.kind() != TokenKind::LParen {
+			return Cst::Atom(t);
+		}
+
+		let open      = t;
+		let mut items = vec![];
+
+		loop {
+			// if we run out of tokens without a ')', the list is unclosed. the Eof is left for
+			// parse() to pick up, so its trivia isn't lost.
+			if Cst::at_eof(tokens) {
+				return Cst::List { open, items, close: None };
+			}
+
+			// a ')' ends the list; anything else is an item in it.
+			match Cst::parse_one(tokens) {
+				Cst::Atom(t) if *t.kind() == TokenKind::RParen =>
+					return Cst::List { open, items, close: Some(t) },
+
+				item => items.push(item),
+			}
+		}
+	}
+
+	fn at_eof(tokens: &mut Peekable<impl Iterator<Item = LosslessToken>>) -> bool {
+		return tokens.peek().map(|t| *t.kind() == TokenKind::Eof).unwrap_or(true);
+	}
+
+	// Turns this tree back into source code, trivia and all.
+	pub fn to_source(&self) -> String {
+		let mut ret = String::new();
+		self.write_to(&mut ret);
+		return ret;
+	}
+
+	fn write_to(&self, out: &mut String) {
+		match self {
+			Cst::Atom(t) => t.write_to(out),
+
+			Cst::List { open, items, close } => {
+				open.write_to(out);
+
+				for item in items {
+					item.write_to(out);
+				}
+
+				if let Some(close) = close {
+					close.write_to(out);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,414 @@
+// This is another lexer for the exact same token grammar as the one in lib.rs, but it's written
+// to be fast on big inputs. The main Lexer is easy to read, but it does two wasteful things:
+//
+// - it copies the whole source into a Vec<char>, which is 4 bytes per character, even though
+//   most source code is ASCII and is only 1 byte per character in the &str.
+// - it copies every identifier into a String (and every string literal into a new one), even
+//   though that text is *already sitting right there* in the source code.
+//
+// This lexer avoids both. It walks through the &str by *byte offset*, and its tokens *borrow*
+// their text from the source instead of copying it. That's what the 'src lifetime is about:
+// a Token<'src> can't outlive the source string it points into. The compiler checks that for us!
+//
+// (Run "cargo bench" to see how much faster it is.)
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use unicode_normalization::{ is_nfc, UnicodeNormalization };
+
+use crate::{ is_ident_cont, is_ident_start, LexError, SourceMap, Span };
+
+// ------------------------------------------------------------------------------------------------
+// Token type
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenKind<'src> {
+	Eof,
+	LParen,
+	RParen,
+	Quote,
+	Dot,
+	Id(Cow<'src, str>), // borrowed, unless it had to be normalized (see lex_ident() in lib.rs).
+	IntLit(i64),
+	FloatLit(f64),
+
+	// Cow ("clone on write") is either Borrowed(&str) or Owned(String). Most strings don't have
+	// any escape sequences, so they can just borrow from the source. Only strings with escapes
+	// need a new String, since "\n" in the source is two characters but one in the string.
+	StrLit(Cow<'src, str>),
+
+	// only produced if you ask for it with Lexer::with_comments.
+	Comment(&'src str),
+}
+
+#[derive(Debug, Clone)]
+pub struct Token<'src> {
+	pub span: Range<usize>, // the *byte* range in the source, so &source[span] is the token's text.
+	pub kind: TokenKind<'src>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// The Lexer type
+// ------------------------------------------------------------------------------------------------
+
+pub struct Lexer<'src> {
+	source:        &'src str,
+	pos:           usize, // this is a byte offset, not a codepoint index!
+	done:          bool,
+	keep_comments: bool,
+}
+
+impl<'src> Lexer<'src> {
+	pub fn new(source: &'src str) -> Self {
+		return Lexer { source, pos: 0, done: false, keep_comments: false };
+	}
+
+	// Same as the main Lexer's with_comments.
+	pub fn with_comments(mut self) -> Self {
+		self.keep_comments = true;
+		return self;
+	}
+
+	// The rest of the source that hasn't been lexed yet.
+	fn rest(&self) -> &'src str {
+		return &self.source[self.pos ..];
+	}
+
+	fn cur(&self) -> Option<char> {
+		return self.rest().chars().next();
+	}
+
+	fn peek_char(&self, n: usize) -> Option<char> {
+		return self.rest().chars().nth(n);
+	}
+
+	// Moves forward past one character. Characters can be 1 to 4 bytes long in UTF-8, so we
+	// can't just do pos += 1 like the main lexer does.
+	fn advance(&mut self) {
+		if let Some(c) = self.cur() {
+			self.pos += c.len_utf8();
+		}
+	}
+
+	// Moves forward past every character for which pred returns true. char_indices() gives
+	// each char along with its byte offset, so when we find the first one that doesn't match,
+	// we know exactly how many bytes to skip.
+	fn skip_while(&mut self, pred: impl Fn(char) -> bool) {
+		match self.rest().char_indices().find(|&(_, c)| !pred(c)) {
+			Some((offset, _)) => self.pos += offset,
+			None              => self.pos = self.source.len(),
+		}
+	}
+
+	// Errors are rare, so it's fine to do something slow here. We build a SourceMap only when
+	// there's an error, so that the error has the same kind of Span as the main lexer's errors.
+	fn span(&self, start: usize, end: usize) -> Span {
+		let map = SourceMap::new(self.source);
+		return map.span(map.byte_to_char(start), map.byte_to_char(end));
+	}
+
+	fn token(&self, start: usize, kind: TokenKind<'src>) -> Token<'src> {
+		return Token { span: start .. self.pos, kind };
+	}
+
+	fn lex_token(&mut self) -> Result<Token<'src>, LexError> {
+		// Trivia
+		loop {
+			self.skip_while(|c| matches!(c, ' ' | '\t' | '\n'));
+			let start = self.pos;
+
+			match (self.cur(), self.peek_char(1)) {
+				(Some(';'), _)         => self.skip_while(|c| c != '\n'),
+				(Some('#'), Some('|')) => self.skip_block_comment()?,
+				_                      => break,
+			}
+
+			if self.keep_comments {
+				return Ok(self.token(start, TokenKind::Comment(&self.source[start .. self.pos])));
+			}
+		}
+
+		let start = self.pos;
+
+		let c = match self.cur() {
+			Some(c) => c,
+			None    => return Ok(self.token(start, TokenKind::Eof)),
+		};
+
+		match c {
+			'('  => { self.advance(); return Ok(self.token(start, TokenKind::LParen)); }
+			')'  => { self.advance(); return Ok(self.token(start, TokenKind::RParen)); }
+			'\'' => { self.advance(); return Ok(self.token(start, TokenKind::Quote));  }
+			'.'  => { self.advance(); return Ok(self.token(start, TokenKind::Dot));    }
+			'"'  => return self.lex_string(),
+
+			// here's the zero-copy part: the identifier is just a slice of the source. only
+			// identifiers that aren't already in NFC have to be copied. (this lexer doesn't give
+			// warnings about identifiers like the main one does.)
+			c if is_ident_start(c) => {
+				self.skip_while(is_ident_cont);
+				let text = &self.source[start .. self.pos];
+
+				let name = if is_nfc(text) { Cow::Borrowed(text) }
+				           else            { Cow::Owned(text.nfc().collect()) };
+
+				return Ok(self.token(start, TokenKind::Id(name)));
+			}
+
+			c if c.is_ascii_digit() => return self.lex_number(),
+
+			c => return Err(LexError::InvalidChar(self.span(start, start + c.len_utf8()), c)),
+		}
+	}
+
+	fn skip_block_comment(&mut self) -> Result<(), LexError> {
+		let mut opened = vec![];
+
+		loop {
+			match (self.cur(), self.peek_char(1)) {
+				(Some('#'), Some('|')) => {
+					opened.push(self.pos);
+					self.pos += 2;
+				}
+
+				(Some('|'), Some('#')) => {
+					opened.pop();
+					self.pos += 2;
+
+					if opened.is_empty() {
+						return Ok(());
+					}
+				}
+
+				(Some(_), _) => self.advance(),
+
+				(None, _) => {
+					let start = *opened.last().expect("at least one comment is open");
+					return Err(LexError::UnterminatedComment(self.span(start, start + 2)));
+				}
+			}
+		}
+	}
+
+	fn lex_string(&mut self) -> Result<Token<'src>, LexError> {
+		let start = self.pos;
+		self.pos += 1; // skip the opening '"'.
+
+		// if we never see a '\', we never have to allocate anything.
+		let mut owned: Option<String> = None;
+
+		loop {
+			let c = match self.cur() {
+				Some(c) => c,
+				None    => return Err(LexError::UnterminatedString(self.span(start, start + 1))),
+			};
+
+			match c {
+				'"' => break,
+
+				'\\' => {
+					// the first escape we see means we need a String after all. everything up
+					// to here gets copied in once, then we keep adding to it.
+					let s = owned.get_or_insert_with(|| self.source[start + 1 .. self.pos].into());
+					let escape_start = self.pos;
+					self.pos += 1;
+
+					let e = match self.cur() {
+						Some(e) => e,
+						None    => continue, // unterminated; the next time around will report it.
+					};
+
+					self.advance();
+
+					match e {
+						'n'  => s.push('\n'),
+						't'  => s.push('\t'),
+						'\\' => s.push('\\'),
+						'"'  => s.push('"'),
+						'u'  => s.push(self.lex_unicode_escape(escape_start)?),
+						_    => return Err(LexError::InvalidEscape(self.span(escape_start, self.pos), e)),
+					}
+				}
+
+				c => {
+					if let Some(s) = owned.as_mut() {
+						s.push(c);
+					}
+
+					self.advance();
+				}
+			}
+		}
+
+		let text = match owned {
+			Some(s) => Cow::Owned(s),
+			None    => Cow::Borrowed(&self.source[start + 1 .. self.pos]),
+		};
+
+		self.pos += 1; // skip the closing '"'.
+		return Ok(self.token(start, TokenKind::StrLit(text)));
+	}
+
+	// '\u{' HexDigit+ '}', where the "\u" has already been skipped.
+	fn lex_unicode_escape(&mut self, start: usize) -> Result<char, LexError> {
+		if self.cur() != Some('{') {
+			return Err(LexError::InvalidUnicodeEscape(self.span(start, self.pos)));
+		}
+
+		self.pos += 1;
+		let hex_start = self.pos;
+		self.skip_while(|c| c.is_ascii_hexdigit());
+		let hex = &self.source[hex_start .. self.pos];
+
+		if self.cur() != Some('}') {
+			return Err(LexError::InvalidUnicodeEscape(self.span(start, self.pos)));
+		}
+
+		self.pos += 1;
+
+		if hex.is_empty() || hex.len() > 6 {
+			return Err(LexError::InvalidUnicodeEscape(self.span(start, self.pos)));
+		}
+
+		let value = u32::from_str_radix(hex, 16).expect("only hex digits were skipped");
+
+		match char::from_u32(value) {
+			Some(c) => return Ok(c),
+			None    => return Err(LexError::InvalidUnicodeEscape(self.span(start, self.pos))),
+		}
+	}
+
+	fn lex_number(&mut self) -> Result<Token<'src>, LexError> {
+		let start = self.pos;
+
+		let radix = match (self.cur(), self.peek_char(1)) {
+			(Some('0'), Some('x')) => 16,
+			(Some('0'), Some('o')) => 8,
+			(Some('0'), Some('b')) => 2,
+			_                      => 10,
+		};
+
+		if radix != 10 {
+			self.pos += 2;
+			let digits = self.lex_digits(radix)?;
+
+			if digits.is_empty() {
+				let prefix = self.source[start .. start + 2].into();
+				return Err(LexError::MissingRadixDigits(self.span(start, self.pos), prefix));
+			}
+
+			match i64::from_str_radix(&digits, radix) {
+				Ok(value) => return Ok(self.token(start, TokenKind::IntLit(value))),
+				Err(..)   => return Err(self.int_out_of_range(start)),
+			}
+		}
+
+		let int_part = self.lex_digits(10)?;
+		let mut is_float = false;
+
+		if self.cur() == Some('.') {
+			is_float = true;
+			self.pos += 1;
+
+			if self.lex_digits(10)?.is_empty() {
+				return Err(LexError::MissingFraction(self.span(start, self.pos)));
+			}
+		}
+
+		if let Some('e') | Some('E') = self.cur() {
+			is_float = true;
+			self.pos += 1;
+
+			if let Some('+') | Some('-') = self.cur() {
+				self.pos += 1;
+			}
+
+			if self.lex_digits(10)?.is_empty() {
+				return Err(LexError::MissingExponent(self.span(start, self.pos)));
+			}
+		}
+
+		if let Some(c) = self.cur() {
+			if is_ident_cont(c) {
+				return Err(LexError::InvalidChar(self.span(self.pos, self.pos + c.len_utf8()), c));
+			}
+		}
+
+		if is_float {
+			// Rust's float parser understands everything but the '_' separators.
+			let text = self.source[start .. self.pos].replace('_', "");
+
+			match text.parse::<f64>() {
+				Ok(value) if value.is_finite() => return Ok(self.token(start, TokenKind::FloatLit(value))),
+				_ => {
+					let text = self.source[start .. self.pos].into();
+					return Err(LexError::FloatOutOfRange(self.span(start, self.pos), text));
+				}
+			}
+		} else {
+			match int_part.parse::<i64>() {
+				Ok(value) => return Ok(self.token(start, TokenKind::IntLit(value))),
+				Err(..)   => return Err(self.int_out_of_range(start)),
+			}
+		}
+	}
+
+	// Skips a run of digits and '_' separators. The digits are usually borrowed from the source;
+	// only numbers that actually *have* separators need a new String with them taken out.
+	fn lex_digits(&mut self, radix: u32) -> Result<Cow<'src, str>, LexError> {
+		let start = self.pos;
+		self.skip_while(|c| c.is_digit(radix) || c == '_');
+		let digits = &self.source[start .. self.pos];
+
+		// same rules as the main lexer for what can't come after the digits.
+		if let Some(c) = self.cur() {
+			let is_exponent = radix == 10 && (c == 'e' || c == 'E');
+
+			if c.is_alphanumeric() && !is_exponent {
+				let span = self.span(self.pos, self.pos + c.len_utf8());
+
+				if radix == 10 {
+					return Err(LexError::InvalidChar(span, c));
+				} else {
+					return Err(LexError::InvalidDigit(span, c, radix));
+				}
+			}
+		}
+
+		if digits.contains('_') {
+			return Ok(Cow::Owned(digits.replace('_', "")));
+		} else {
+			return Ok(Cow::Borrowed(digits));
+		}
+	}
+
+	fn int_out_of_range(&self, start: usize) -> LexError {
+		return LexError::IntOutOfRange(self.span(start, self.pos), self.source[start .. self.pos].into());
+	}
+}
+
+impl<'src> Iterator for Lexer<'src> {
+	type Item = Result<Token<'src>, LexError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let ret = self.lex_token();
+
+		match &ret {
+			Ok(Token { kind: TokenKind::Eof, .. }) | Err(..) => self.done = true,
+			_ => {}
+		}
+
+		return Some(ret);
+	}
+}
+
+// Just like the main lex(), but the tokens borrow from source.
+pub fn lex(source: &str) -> Result<Vec<Token<'_>>, LexError> {
+	return Lexer::new(source).collect();
+}
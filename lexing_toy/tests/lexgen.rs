@@ -0,0 +1,77 @@
+// Checks that the lexer that lexgen builds from lisp_rules() gives the same tokens as the
+// hand-written lexer, on some samples and on the files in corpus/. Run it with:
+//
+//     cargo test --test lexgen
+//
+// If you change one of the lexers, this is how you find out if you forgot to change the other.
+
+use std::path::Path;
+
+use lexing_toy::lexgen::*;
+use lexing_toy::*;
+
+// code that both lexers should lex the same way.
+const SAMPLES: &[&str] = &[
+	"(add 1 2)",
+	"(define (square x) (mul x x)) ; a comment\n(square 12)",
+	"(list 0xFF 0x_ff 0o777 0b1010_1010 1_000_000 0)",
+	"(list 3.14 1e10 2.5E-3 0.5 1_0.0_1 7e+2)",
+	"(print \"hello\" \"tab\\tnewline\\n\" \"quote \\\" backslash \\\\\" \"\\u{1F600}\")",
+	"(définir café \"☕\") (λ (x) x) _under_score x1y2",
+	"#| a block comment |# (a #| another one |# b) #|||#",
+	"\t\n  (  )\n",
+	"'x '(1 2) (a . b) (1.5 . 2.5)",
+	"",
+];
+
+// the files in corpus/ that don't have any mistakes or nested comments in them. (regexes can't
+// count, so the generated lexer can't do nested comments.)
+const CORPUS: &[&str] = &["simple.lisp", "literals.lisp", "unicode.lisp"];
+
+#[test]
+fn generated_lexer_agrees_with_hand_written_one() {
+	let lexer = lisp_lexer();
+	let mut failures = Vec::new();
+
+	for sample in SAMPLES {
+		if let Some(failure) = compare(&lexer, sample) {
+			failures.push(failure);
+		}
+	}
+
+	for name in CORPUS {
+		let path   = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus").join(name);
+		let source = std::fs::read_to_string(path).expect("couldn't read a corpus file");
+
+		if let Some(failure) = compare(&lexer, &source) {
+			failures.push(failure);
+		}
+	}
+
+	// all the failures are shown at once, instead of stopping at the first one.
+	assert!(failures.is_empty(), "the lexers disagree:\n{}", failures.join("\n"));
+}
+
+// Lexes source with both lexers. If they disagree, gives back a message saying how.
+fn compare(lexer: &TableLexer, source: &str) -> Option<String> {
+	let expected = show(lex(source));
+	let got      = show(lexer.lex(source));
+
+	if got == expected {
+		return None;
+	} else {
+		return Some(format!("{:?}\n    hand-written: {}\n    generated:    {}", source, expected, got));
+	}
+}
+
+// The tokens as a string, so they're easy to compare and print.
+fn show(tokens: Result<Vec<Token>, LexError>) -> String {
+	match tokens {
+		Ok(tokens) => {
+			let kinds = tokens.iter().map(|t| format!("{:?}@{}", t.kind, t.span)).collect::<Vec<_>>();
+			return kinds.join(" ");
+		}
+
+		Err(e) => return format!("error: {}", e),
+	}
+}
@@ -0,0 +1,116 @@
+// Checks the incremental Relexer against lexing the whole thing again. After every edit, the
+// Relexer's tokens have to be exactly the same as what lex_recovering() gives for the whole new
+// source, and its SourceMap has to be the same as a brand new one. Run it with:
+//
+//     cargo test --test relex
+
+use lexing_toy::*;
+
+// the little bits of code that the random edits insert. some of them are "dangerous," like the
+// '"' and '#|', which can change the meaning of everything after them!
+const SNIPPETS: &[&str] = &[
+	"(", ")", " ", "\n", "x", "add", "123", "0x", "FF", "1.5", "e", ".", "\"", "\"hi\"", "\\",
+	";", "; comment\n", "#|", "|#", "#", "|", "$", "_", "é", "\u{301}", "😀", "\t", "'",
+];
+
+const NUM_EDITS: usize = 500;
+
+#[test]
+fn random_edits() {
+	let mut rng     = Rng(12345);
+	let mut relexer = Relexer::new(&make_source(200));
+
+	for i in 0 .. NUM_EDITS {
+		let edit = random_edit(&mut rng, relexer.source());
+		check_edit(&mut relexer, &edit, &format!("edit #{}", i));
+	}
+}
+
+#[test]
+fn typing_a_quote_changes_everything_after_it() {
+	let mut relexer = Relexer::new("(a b)\n(c d)\n");
+
+	// the '"' starts a string that never ends, so nothing after it can be copied. the tokens are
+	// '(', an Error for the string, and Eof, and all of them had to be lexed.
+	let lexed = check_edit(&mut relexer, &Edit::new(1 .. 1, "\""), "typing the '\"'");
+	assert_eq!(relexer.tokens().len(), 3, "tokens: {:?}", relexer.tokens());
+	assert_eq!(lexed, 3);
+
+	// and deleting it puts everything back.
+	check_edit(&mut relexer, &Edit::new(1 .. 2, ""), "deleting the '\"'");
+	assert_eq!(relexer.source(), "(a b)\n(c d)\n");
+}
+
+#[test]
+fn only_lexes_around_the_edit() {
+	let mut relexer = Relexer::new(&make_source(100));
+
+	// changing "x" to "xyz" on line 50 only has to lex a couple of tokens, not thousands.
+	let start = relexer.source_map().index_of(50, 15).expect("line 50 is that long");
+	let lexed = check_edit(&mut relexer, &Edit::new(start .. start + 1, "xyz"), "renaming x");
+	assert!(lexed <= 3, "lexed {} tokens", lexed);
+}
+
+#[test]
+fn edits_at_the_ends() {
+	let mut relexer = Relexer::new("(add 1 2)");
+	check_edit(&mut relexer, &Edit::new(0 .. 0, "(mul 3 "), "inserting at the start");
+	check_edit(&mut relexer, &Edit::new(16 .. 16, ")\n"), "inserting at the end");
+	check_edit(&mut relexer, &Edit::new(0 .. 18, ""), "deleting everything");
+	assert_eq!(relexer.tokens().len(), 1, "only the Eof should be left");
+}
+
+// Makes the edit, and checks that the Relexer agrees with lexing the whole new source. Returns
+// how many tokens the Relexer lexed.
+fn check_edit(relexer: &mut Relexer, edit: &Edit, what: &str) -> usize {
+	let new_source = edit.apply(relexer.source());
+	let lexed      = relexer.edit(edit);
+
+	assert_eq!(relexer.source(), new_source, "{}: the source is wrong", what);
+
+	let expected = lex_recovering(&new_source).0;
+
+	for (i, (got, exp)) in relexer.tokens().iter().zip(&expected).enumerate() {
+		assert!(got.span == exp.span && got.kind == exp.kind,
+			"{}: token #{} of {:?}: got {:?}, expected {:?}", what, i, new_source, got, exp);
+	}
+
+	assert_eq!(relexer.tokens().len(), expected.len(), "{}: wrong number of tokens", what);
+	assert_eq!(*relexer.source_map(), SourceMap::new(&new_source), "{}: the SourceMap is wrong", what);
+	return lexed;
+}
+
+// Some code to start with: lots of lines of nested function calls.
+fn make_source(lines: usize) -> String {
+	let mut ret = String::new();
+
+	for i in 0 .. lines {
+		ret += &format!("(define (f{} x) (add x {} \"str {}\")) ; line {}\n", i, i * 7, i, i);
+	}
+
+	return ret;
+}
+
+// Either inserts a snippet, deletes a few characters, or replaces a few characters with a snippet.
+fn random_edit(rng: &mut Rng, source: &str) -> Edit {
+	let len   = source.chars().count();
+	let start = rng.below(len + 1);
+	let end   = match rng.below(3) {
+		0 => start,                                    // insert.
+		_ => (start + rng.below(4)).min(len),          // delete or replace a few characters.
+	};
+
+	let text = if rng.below(4) == 0 { "" } else { SNIPPETS[rng.below(SNIPPETS.len())] };
+	return Edit::new(start .. end, text);
+}
+
+// A tiny random number generator (an LCG), so that every run does the same edits, and we don't
+// need a whole crate just for this.
+struct Rng(u64);
+
+impl Rng {
+	fn below(&mut self, n: usize) -> usize {
+		self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		return ((self.0 >> 33) as usize) % n;
+	}
+}
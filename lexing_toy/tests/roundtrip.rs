@@ -0,0 +1,43 @@
+// Checks that lossless lexing really is lossless. For every file in the corpus/ directory, this
+// lexes it with lex_lossless(), builds the Cst, and makes sure that both of them turn back into
+// exactly the same text as the file. Run it with:
+//
+//     cargo test --test roundtrip
+//
+// If you find some code that breaks it, put it in a new file in corpus/ so it stays fixed!
+
+use std::path::Path;
+
+use lexing_toy::*;
+
+#[test]
+fn corpus_round_trips() {
+	// CARGO_MANIFEST_DIR is the directory with Cargo.toml in it, so this works no matter what
+	// directory the test is run from.
+	let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+
+	let mut files = std::fs::read_dir(&corpus)
+		.expect("couldn't read the corpus directory")
+		.map(|entry| entry.expect("couldn't read a corpus file").path())
+		.filter(|path| path.extension().is_some_and(|ext| ext == "lisp"))
+		.collect::<Vec<_>>();
+
+	// read_dir() gives the files in whatever order the OS feels like. sorting makes the failures
+	// come out the same every time.
+	files.sort();
+	assert!(!files.is_empty(), "there are no .lisp files in {}", corpus.display());
+
+	for path in &files {
+		let name   = path.file_name().unwrap().to_string_lossy();
+		let source = std::fs::read_to_string(path).expect("couldn't read a corpus file");
+
+		let (tokens, _errors) = lex_lossless(&source);
+		let from_tokens = to_source(&tokens);
+		assert_eq!(from_tokens, source, "{}: the tokens gave back different text", name);
+
+		let (csts, eof) = Cst::parse(tokens);
+		let mut from_cst = csts.iter().map(Cst::to_source).collect::<String>();
+		from_cst += &eof.full_text();
+		assert_eq!(from_cst, source, "{}: the CST gave back different text", name);
+	}
+}
@@ -2,35 +2,16 @@
 use std::fmt::{ Debug, Display, Formatter, Result as FmtResult };
 
 // ------------------------------------------------------------------------------------------------
-// TokenKind type
+// Tokens
 // ------------------------------------------------------------------------------------------------
 
-/*
-These tokens would be produced by a lexer... IF WE HAD ONE
-Well, another example has one. Maybe you can put the two examples together!
-*/
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum TokenKind {
-	Eof,
-	LParen,
-	RParen,
-	Id(String),
-	IntLit(i64),
-}
+// The tokens come from the tokens crate, which is shared with the lexer in lexing_toy. So the
+// parser reads exactly what the lexer makes, spans and all. They're re-exported so that users of
+// this crate can say parsing_lisp::TokenKind.
+pub use tokens::*;
 
-impl Display for TokenKind {
-	fn fmt(&self, f: &mut Formatter) -> FmtResult {
-		use TokenKind::*;
-
-		match self {
-			Eof       => write!(f, ""),
-			LParen    => write!(f, "("),
-			RParen    => write!(f, ")"),
-			Id(id)    => write!(f, "{}", id),
-			IntLit(i) => write!(f, "{}", i),
-		}
-	}
-}
+use diagnostics::Diagnostic;
+use lexing_toy::LexError;
 
 // ------------------------------------------------------------------------------------------------
 // AST type
@@ -39,23 +20,43 @@ impl Display for TokenKind {
 /*
 Here is the full syntactic grammar for this language. Remember that the "alphabet" (set of symbols
 that this grammar operates on) is *tokens* produced by the lexer. This is what the rules for Id,
-Num, and Eof mean. '(' and ')' are also the LParen and RParen tokens, but to make the rules more
-readable, we just write them explicitly.
+Num, Str, and Eof mean. '(', ')', '\'', and '.' are also the LParen, RParen, Quote, and Dot tokens,
+but to make the rules more readable, we just write them explicitly.
 
-	Program:  Exp Eof
-	Exp:      Id | Num | ParenExp
-	ParenExp: '(' Exp+ ')'
+	Program:  Exp* Eof
+	Exp:      Id | Num | Str | QuoteExp | ParenExp
+	QuoteExp: '\'' Exp
+	ParenExp: '(' ')' | '(' Exp+ ('.' Exp)? ')'
 
 	Id:  <Id token from lexing phase>
 	Num: <IntLit token from lexing phase>
+	Str: <StrLit token from lexing phase>
 	Eof: <'<eof>' token from lexing phase>
+
+A few of these are shorthands that real Lisps have too:
+
+- 'x is short for (quote x). the parser turns it into that, so there's no Exp for it.
+- () is the empty list. it's just a Parens with nothing in it.
+- (a b . c) is a *dotted* list: a list whose end isn't (), but c. (1 . 2) is a dotted pair, which
+  is what Lisp's cons makes. so (a b . c) is (cons a (cons b c)), and (a b) is
+  (cons a (cons b ())).
+
+And one thing that's missing on purpose: floats. The lexer makes FloatLit tokens, but Num is only
+IntLit, and there's no Exp for floats (the interpreter in eval_lisp only does integer math). So a
+float like 1.5 is a parse error: "expected an expression, found '1.5'".
 */
 
 #[derive(Clone)]
 pub enum Exp {
 	Id(String),
 	Num(i64),
+	Str(String),
 	Parens(Vec<Box<Exp>>),
+	Dotted(Vec<Box<Exp>>, Box<Exp>), // (a b . c) is Dotted([a, b], c). the Vec is never empty.
+
+	// Only made in recovering mode (see Parser::parse_recovering()). It goes where an expression
+	// should have been, but wasn't, so that the rest of the tree can still be built.
+	Error,
 }
 
 impl Exp {
@@ -67,9 +68,21 @@ impl Exp {
 		return Box::new(Exp::Num(i));
 	}
 
+	pub fn new_str(s: &str) -> Box<Self> {
+		return Box::new(Exp::Str(s.into()));
+	}
+
 	pub fn new_parens(exps: Vec<Box<Exp>>) -> Box<Self> {
 		return Box::new(Exp::Parens(exps));
 	}
+
+	pub fn new_dotted(exps: Vec<Box<Exp>>, tail: Box<Exp>) -> Box<Self> {
+		return Box::new(Exp::Dotted(exps, tail));
+	}
+
+	pub fn new_error() -> Box<Self> {
+		return Box::new(Exp::Error);
+	}
 }
 
 // You can write your own implementations of Debug too, instead of #[derive]ing them.
@@ -81,41 +94,218 @@ impl Debug for Exp {
 		match self {
 			Id(id) => write!(f, "Id({})", id),
 			Num(i) => write!(f, "Num({})", i),
+			Str(s) => write!(f, "Str({:?})", s),
 			Parens(exps) => {
 				write!(f, "Parens")?;
 				f.debug_list().entries(exps.iter()).finish()
 			}
+			Dotted(exps, tail) => f.debug_tuple("Dotted").field(exps).field(tail).finish(),
+			Error => write!(f, "Error"),
+		}
+	}
+}
+
+// Display prints an Exp the way it would be written in the source code, as an S-expression.
+// Parsing what this prints gives you the same AST back (unless there's an Error in it, which has no
+// source code, so it prints as "<error>").
+impl Display for Exp {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		use Exp::*;
+
+		match self {
+			Id(id) => write!(f, "{}", id),
+			Num(i) => write!(f, "{}", i),
+			Str(s) => write_str_lit(f, s),
+			Parens(exps) => {
+				write!(f, "(")?;
+				write_spaced(f, exps)?;
+				write!(f, ")")
+			}
+			Dotted(exps, tail) => {
+				write!(f, "(")?;
+				write_spaced(f, exps)?;
+				write!(f, " . {})", tail)
+			}
+			Error => write!(f, "<error>"),
+		}
+	}
+}
+
+// Writes some expressions with spaces between them.
+fn write_spaced(f: &mut Formatter, exps: &[Box<Exp>]) -> FmtResult {
+	for (i, exp) in exps.iter().enumerate() {
+		// a space goes *between* the expressions, so not before the first one.
+		if i > 0 {
+			write!(f, " ")?;
 		}
+
+		write!(f, "{}", exp)?;
 	}
+
+	return Ok(());
+}
+
+// Writes a string with quotes around it, using the lexer's escape sequences. ({:?} is close, but
+// it uses some escapes that the lexer doesn't know, like \r.)
+fn write_str_lit(f: &mut Formatter, s: &str) -> FmtResult {
+	write!(f, "\"")?;
+
+	for c in s.chars() {
+		match c {
+			'"'                 => write!(f, "\\\"")?,
+			'\\'                => write!(f, "\\\\")?,
+			'\n'                => write!(f, "\\n")?,
+			'\t'                => write!(f, "\\t")?,
+			c if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32)?,
+			c                   => write!(f, "{}", c)?,
+		}
+	}
+
+	return write!(f, "\"");
 }
 
 // ------------------------------------------------------------------------------------------------
 // ParseError type
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
-pub enum ParseError {
-	ExpectedExpression,
-	ExpectedLParen,
-	ExpectedRParen,
-	ExpectedEof,
+// What the parser was looking for when it found something else.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+	Expression,   // any expression: an Id, a Num, a Str, a QuoteExp, or a ParenExp.
+	LParen,       // a '(' to start a ParenExp.
+	Dot,          // a '.' to make a dotted list.
+	RParen(Span), // a ')' to close the '(' at this span.
+	Eof,          // the end of the input.
+}
+
+impl Display for Expected {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			Expected::Expression   => write!(f, "an expression"),
+			Expected::LParen       => write!(f, "'('"),
+			Expected::Dot          => write!(f, "'.'"),
+			Expected::RParen(open) => write!(f, "')' to close '(' opened at {}", open),
+			Expected::Eof          => write!(f, "end of input"),
+		}
+	}
+}
+
+// A parse error always means "I was expecting one of these things, but I found that token
+// instead." So instead of one variant per kind of mistake, it holds the expected things and the
+// token that was found. That's enough to make a pretty good message, like:
+//
+//     expected ')' to close '(' opened at 1:3, found end of input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+	pub index:    usize,         // the index of the token that was found (in the parser's tokens).
+	pub span:     Span,          // where that token is.
+	pub found:    TokenKind,     // what that token is.
+	pub expected: Vec<Expected>, // what could have gone there instead. it's never empty.
+}
+
+impl ParseError {
+	// Like LexError::to_diagnostic(), this makes a Diagnostic which points at the token that was
+	// found. The SourceMap has to be the one from the lexer that made the tokens.
+	pub fn to_diagnostic(&self, map: &SourceMap) -> Diagnostic {
+		let ret = Diagnostic::error(&self.to_string(), map.byte_range(self.span));
+
+		// only give help when there's one obvious fix.
+		match (self.expected.as_slice(), &self.found) {
+			([Expected::RParen(_)], _) =>
+				return ret.with_help("add a ')' after the last expression in the parentheses"),
+			([Expected::Expression, Expected::Eof], TokenKind::RParen) =>
+				return ret.with_note("this ')' doesn't have a '(' to close"),
+			_ =>
+				return ret,
+		}
+	}
 }
 
 impl Display for ParseError {
 	fn fmt(&self, f: &mut Formatter) -> FmtResult {
-		use ParseError::*;
+		write!(f, "expected ")?;
 
-		match self {
-			ExpectedExpression => write!(f, "expected an expression"),
-			ExpectedLParen     => write!(f, "expected '(' to start an expression"),
-			ExpectedRParen     => write!(f, "expected ')' to end an expression"),
-			ExpectedEof        => write!(f, "expected end-of-file token at end of input"),
+		// "a", "a or b", "a, b, or c" - the way you'd say it in English.
+		let n = self.expected.len();
+
+		for (i, e) in self.expected.iter().enumerate() {
+			if i > 0 && n > 2 {
+				write!(f, ",")?;
+			}
+
+			if i > 0 && i == n - 1 {
+				write!(f, " or ")?;
+			} else if i > 0 {
+				write!(f, " ")?;
+			}
+
+			write!(f, "{}", e)?;
 		}
+
+		write!(f, ", found {}", describe_token(&self.found))
+	}
+}
+
+// How a token is shown in "found ..." in an error message.
+fn describe_token(kind: &TokenKind) -> String {
+	match kind {
+		TokenKind::Eof       => return "end of input".into(),
+		TokenKind::Newline   => return "a newline".into(),
+		TokenKind::Indent    => return "an indent".into(),
+		TokenKind::Dedent    => return "a dedent".into(),
+		TokenKind::Error     => return "a bad token".into(),
+		TokenKind::StrLit(_) => return format!("{}", kind), // it already has quotes around it.
+		_                    => return format!("'{}'", kind),
 	}
 }
 
 impl std::error::Error for ParseError {}
 
+// ------------------------------------------------------------------------------------------------
+// Error type
+// ------------------------------------------------------------------------------------------------
+
+// Parser::parse_str() lexes *and* parses, so it can fail in either step. This enum can hold
+// either kind of error, so the caller only has one thing to check.
+#[derive(Debug)]
+pub enum Error {
+	Lex(LexError),
+	Parse(ParseError),
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		match self {
+			Error::Lex(e)   => write!(f, "lexing error: {}", e),
+			Error::Parse(e) => write!(f, "parse error: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	// source() is how an error says "I happened because of this other error."
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Lex(e)   => return Some(e),
+			Error::Parse(e) => return Some(e),
+		}
+	}
+}
+
+// These From impls are what let the ? operator turn a LexError or ParseError into an Error
+// automatically. ? calls From::from() on the error before returning it.
+impl From<LexError> for Error {
+	fn from(e: LexError) -> Self {
+		return Error::Lex(e);
+	}
+}
+
+impl From<ParseError> for Error {
+	fn from(e: ParseError) -> Self {
+		return Error::Parse(e);
+	}
+}
+
 // ------------------------------------------------------------------------------------------------
 // The recursive descent parser
 // ------------------------------------------------------------------------------------------------
@@ -124,19 +314,57 @@ impl std::error::Error for ParseError {}
 // it's the same as writing Result<Box<Exp>, ParseError>.
 type ParseResult = Result<Box<Exp>, ParseError>;
 
+// And a whole program is a list of expressions.
+type ProgramResult = Result<Vec<Box<Exp>>, ParseError>;
+
 pub struct Parser<'t> {
-	tokens: &'t [TokenKind],
+	tokens: &'t [Token],
 	pos:    usize,
+
+	// if true, errors don't stop the parser. instead, they are recorded in `errors`, and the
+	// parser patches things up as best it can and keeps going. (like the lexer's recovering mode.)
+	recover: bool,
+
+	// the errors found so far in recovering mode.
+	errors: Vec<ParseError>,
 }
 
 impl<'t> Parser<'t> {
-	pub fn parse(tokens: &'t [TokenKind]) -> ParseResult {
+	// Parses some tokens from the lexer.
+	pub fn parse(tokens: &'t [Token]) -> ProgramResult {
 		let mut p = Parser::new(tokens);
 		return p.parse_program();
 	}
 
-	fn new(tokens: &'t [TokenKind]) -> Self {
-		return Parser { tokens, pos: 0 };
+	// This is like parse(), but it doesn't stop at the first error. Instead, it always returns a
+	// tree *and* a list of all the errors it found. Where something was wrong, the tree is
+	// patched up:
+	//
+	// - where an expression was expected but something else was found, there's an Exp::Error.
+	//   (the bad token is skipped, unless it's a ')' or the end of the input.)
+	// - a '(' that's never closed is closed at the end of the input, as if there were a ')'.
+	// - a ')' that doesn't close anything is skipped.
+	// - anything between the Exp after a '.' and the ')' is skipped.
+	//
+	// Editors want this: the code is almost always broken while you're typing it, but they still
+	// want to know as much as they can about it. If the list is empty, there were no errors!
+	pub fn parse_recovering(tokens: &'t [Token]) -> (Vec<Box<Exp>>, Vec<ParseError>) {
+		let mut p = Parser::new(tokens);
+		p.recover = true;
+
+		let ret = p.parse_program().expect("recovering parser never fails");
+		return (ret, p.errors);
+	}
+
+	// Lexes and parses some source code, all in one go.
+	pub fn parse_str(source: &str) -> Result<Vec<Box<Exp>>, Error> {
+		let tokens = lexing_toy::lex(source)?;
+		let ret    = Parser::parse(&tokens)?;
+		return Ok(ret);
+	}
+
+	fn new(tokens: &'t [Token]) -> Self {
+		return Parser { tokens, pos: 0, recover: false, errors: vec![] };
 	}
 
 	fn next(&mut self) {
@@ -146,48 +374,174 @@ impl<'t> Parser<'t> {
 
 	fn cur(&self) -> TokenKind {
 		if self.pos < self.tokens.len() {
-			return self.tokens[self.pos].clone();
+			return self.tokens[self.pos].kind.clone();
 		} else {
 			return TokenKind::Eof;
 		}
 	}
 
-	// Program: Exp Eof
-	fn parse_program(&mut self) -> ParseResult {
-		let ret = self.parse_exp()?;
+	// Where the current token is. If we've gone past the end of the tokens (which only happens if
+	// there was no Eof token at the end), it's an empty span right after the last one.
+	fn cur_span(&self) -> Span {
+		if let Some(t) = self.tokens.get(self.pos) {
+			return t.span;
+		}
+
+		match self.tokens.last() {
+			Some(t) => return Span::new(t.span.end, t.span.end, t.span.line, t.span.col + t.span.len()),
+			None    => return Span::default(),
+		}
+	}
+
+	// Makes an error about the current token.
+	fn error(&self, expected: Vec<Expected>) -> ParseError {
+		return ParseError { index: self.pos, span: self.cur_span(), found: self.cur(), expected };
+	}
+
+	// Called where the current token is wrong. If we're not recovering, that's an Err, and the ?
+	// after the call makes the parser stop. If we are, the error is recorded, and it returns Ok so
+	// that the caller can patch things up and keep going.
+	fn report(&mut self, expected: Vec<Expected>) -> Result<(), ParseError> {
+		let e = self.error(expected);
+
+		if !self.recover {
+			return Err(e);
+		}
+
+		// the same error can get reported twice in a row, like in "(", where the loop in
+		// parse_paren_exp() and then expect_rparen() both want a ')'. only one is useful. but
+		// different errors at the same token are all kept: at the end of "((", *both* '('s are
+		// missing their ')'s.
+		if self.errors.last() != Some(&e) {
+			self.errors.push(e);
+		}
+
+		return Ok(());
+	}
+
+	// Program: Exp* Eof
+	fn parse_program(&mut self) -> ProgramResult {
+		let mut exps = Vec::new();
+
+		// this is a * rule, so we keep going until we see what comes after it: the Eof.
+		loop {
+			match self.cur() {
+				TokenKind::Eof => break,
+
+				// a ')' can't start an expression, so parse_exp() would stop on it. but out here,
+				// it doesn't close anything, so we skip it when recovering.
+				TokenKind::RParen => {
+					self.report(vec![Expected::Expression, Expected::Eof])?;
+					self.next();
+				}
+
+				_ => exps.push(self.parse_exp()?),
+			}
+		}
+
 		self.expect_eof()?;
-		return Ok(ret);
+		return Ok(exps);
 	}
 
-	// Exp: Id | Num | ParenExp
+	// Exp: Id | Num | Str | QuoteExp | ParenExp
 	fn parse_exp(&mut self) -> ParseResult {
 		use TokenKind::*;
 
 		match self.cur() {
-			Id(s)     => { self.next(); return Ok(Exp::new_id(&s)); }
+			Id(s)     => { self.next(); return Ok(Exp::new_id(s.as_str())); }
 			IntLit(i) => { self.next(); return Ok(Exp::new_num(i)); }
+			StrLit(s) => { self.next(); return Ok(Exp::new_str(&s)); }
+			Quote     => return self.parse_quote_exp(),
 			LParen    => return self.parse_paren_exp(),
-			_         => return Err(ParseError::ExpectedExpression),
+			other     => {
+				self.report(vec![Expected::Expression])?;
+
+				// a ')' or the end of the input might still be useful to whoever called us, so
+				// they're left alone. anything else is skipped, so that we don't get stuck on it.
+				if other != RParen && other != Eof {
+					self.next();
+				}
+
+				return Ok(Exp::new_error());
+			}
 		}
 	}
 
-	// ParenExp: '(' Exp+ ')'
+	// True if the current token can start an Exp. (this has to match what parse_exp() accepts.)
+	fn at_exp_start(&self) -> bool {
+		use TokenKind::*;
+		return matches!(self.cur(), Id(_) | IntLit(_) | StrLit(_) | Quote | LParen);
+	}
+
+	// QuoteExp: '\'' Exp
+	// This is where the shorthand turns into the longhand: 'x gives the same AST as (quote x).
+	fn parse_quote_exp(&mut self) -> ParseResult {
+		self.next(); // skip the '\''.
+		let exp = self.parse_exp()?;
+		return Ok(Exp::new_parens(vec![Exp::new_id("quote"), exp]));
+	}
+
+	// ParenExp: '(' ')' | '(' Exp+ ('.' Exp)? ')'
 	fn parse_paren_exp(&mut self) -> ParseResult {
+		// we remember where the '(' is, so that if the ')' is missing, the error can say which
+		// '(' it was supposed to close. (otherwise, with lots of nested parens, good luck!)
+		let open = self.cur_span();
+
 		// Note the use of ? here. It means, "if expect_lparen() returned an error, then return
 		// that error; otherwise, carry on as usual."
 		self.expect_lparen()?;
 
-		// These next few lines implement the "Exp+" part of the rule. See how we parse 1,
-		// then loop to parse more if there are any. The loop condition of + and * rules can
-		// be a little subtle sometimes, but here it's straightforward.
+		// The two alternatives both start with '(', so we can't tell which one it is until we
+		// see the next token. That's fine: "'(' ')'" is the same as "'(' Exp* ')'" with no Exps,
+		// so this loop handles both. It stops at the ')', or at a '.' if there's been an Exp.
 		let mut exps = Vec::new();
-		exps.push(self.parse_exp()?); // you can use ? in the middle of a line too.
 
-		while self.cur() != TokenKind::RParen {
-			exps.push(self.parse_exp()?); // another possible failure point...
+		loop {
+			match self.cur() {
+				TokenKind::RParen => break,
+
+				// at the end of the input, another expression wouldn't help; only a ')' would.
+				// (when recovering, we stop and pretend there was one.)
+				TokenKind::Eof => {
+					self.report(vec![Expected::RParen(open)])?;
+					break;
+				}
+
+				// the "'.' Exp" part. after that, there can only be the ')'.
+				TokenKind::Dot if !exps.is_empty() => {
+					self.next();
+					let tail = self.parse_exp()?;
+
+					// when recovering, any other expressions before the ')' are skipped.
+					while !matches!(self.cur(), TokenKind::RParen | TokenKind::Eof) {
+						self.report(vec![Expected::RParen(open)])?;
+						self.parse_exp()?;
+					}
+
+					self.expect_rparen(open)?;
+					return Ok(Exp::new_dotted(exps, tail));
+				}
+
+				_ if self.at_exp_start() => exps.push(self.parse_exp()?),
+
+				// anything else is wrong. the error says everything that would have been fine.
+				// when recovering, the bad token becomes an Error in the list, and we keep going.
+				_ => {
+					let mut expected = vec![Expected::Expression];
+
+					if !exps.is_empty() {
+						expected.push(Expected::Dot);
+					}
+
+					expected.push(Expected::RParen(open));
+					self.report(expected)?;
+					self.next();
+					exps.push(Exp::new_error());
+				}
+			}
 		}
 
-		self.expect_rparen()?; // and the last possible failure point.
+		self.expect_rparen(open)?; // and the last possible failure point.
 
 		// and if we made it to the end of this method, everything is Ok()!
 		return Ok(Exp::new_parens(exps));
@@ -199,21 +553,22 @@ impl<'t> Parser<'t> {
 		// Ok(()) is how you say "everything's Ok, but I don't have a value to return"
 		match self.cur() {
 			TokenKind::LParen => { self.next(); return Ok(()); }
-			_                 => return Err(ParseError::ExpectedLParen),
+			_                 => return self.report(vec![Expected::LParen]),
 		}
 	}
 
-	fn expect_rparen(&mut self) -> Result<(), ParseError> {
+	// open is where the '(' that this ')' closes is.
+	fn expect_rparen(&mut self, open: Span) -> Result<(), ParseError> {
 		match self.cur() {
 			TokenKind::RParen => { self.next(); return Ok(()); }
-			_                 => return Err(ParseError::ExpectedRParen),
+			_                 => return self.report(vec![Expected::RParen(open)]),
 		}
 	}
 
 	fn expect_eof(&mut self) -> Result<(), ParseError> {
 		match self.cur() {
 			TokenKind::Eof => return Ok(()),
-			_              => return Err(ParseError::ExpectedEof),
+			_              => return self.report(vec![Expected::Eof]),
 		}
 	}
-}
\ No newline at end of file
+}
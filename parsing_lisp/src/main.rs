@@ -2,65 +2,94 @@
 use parsing_lisp::*;
 
 fn main() {
-	use TokenKind::*;
-
 	// The simplest expression.
-	parse_it(&vec![
-		// test
-		id("test")
-	]);
+	parse_it("test");
 
 	// Any number of expressions can come between parens.
-	parse_it(&vec![
-		// (1 2 3 4 5)
-		LParen, IntLit(1), IntLit(2), IntLit(3), IntLit(4), IntLit(5), RParen
-	]);
+	parse_it("(1 2 3 4 5)");
 
 	// Nested expressions.
-	parse_it(&vec![
-		// (add 3 (sub x y))
-		LParen, id("add"), IntLit(3), LParen, id("sub"), id("x"), id("y"), RParen, RParen
-	]);
-
-	// This demonstrates why the Eof token exists - to avoid having extra stuff at the
-	// end of the input that isn't used.
-	parse_it(&vec![
-		// (extra stuff after this) oops
-		LParen, id("extra"), id("stuff"), id("after"), id("this"), RParen, id("oops")
-	]);
-
-	// Another kind of parse error.
-	parse_it(&vec![
-		// (hi
-		LParen, id("hi"),
-	]);
-}
+	parse_it("(add 3 (sub x y))");
+
+	// A program can have any number of expressions in it, one after another.
+	parse_it("(first thing) (second thing) third");
+
+	// Strings, the empty list, quotes, and dotted lists. Look at how 'x turns into (quote x)!
+	parse_it("(print \"hello\") () '(a b) '()");
+	parse_it("(1 . 2) (a b . c)");
+
+	// Another kind of parse error. The error says which '(' never got closed.
+	parse_it("(hi");
+	parse_it("(add 1\n     (mul 2 3)");
+
+	// A '.' needs one expression before it and one after it.
+	parse_it("(. a)");
+	parse_it("(a . b c)");
+
+	// The lexer knows about floats, but this parser doesn't.
+	parse_it("(add 1 2.5)");
 
-// shorthand.
-fn id(s: &str) -> TokenKind {
-	TokenKind::Id(s.into())
+	// Now that the parser is hooked up to a real lexer, there can be lexing errors too.
+	parse_it("(add 1 $)");
+
+	// A recovering parser keeps going after errors, and gives back a tree anyway, with the
+	// mistakes patched up. Each of these has a few errors in it.
+	parse_recovering("(add 1 2.5 (mul 3)");
+	parse_recovering("(a b)) c)");
+	parse_recovering("(() (f 1.5 . x y");
 }
 
-// &[TokenKind] is to Vec<TokenKind> as &str is to String.
-// &[TokenKind] is a slice type, meaning this function can accept any type which can be
-// sliced (including Vecs and arrays).
-fn parse_it(tokens: &[TokenKind]) {
-	show_tokens(&tokens);
+// Parser::parse_str() lexes and then parses. It could fail at either step, but either way, the
+// error is a parsing_lisp::Error, so we only have one thing to check.
+fn parse_it(source: &str) {
+	println!("Input: {}", source);
+
+	// parse_str() does this too, but it's nice to see the tokens in between the two steps.
+	if let Ok(tokens) = lexing_toy::lex(source) {
+		show_tokens(&tokens);
+	}
 
-	match Parser::parse(&tokens) {
+	match Parser::parse_str(source) {
 		Ok(ast)  => println!("AST: {:#?}", ast),
-		Err(err) => println!("parse error: {}", err),
+		Err(err) => println!("{}", err),
 	}
 
 	println!();
 }
 
-fn show_tokens(tokens: &[TokenKind]) {
-	print!("Input tokens: ");
+// Parser::parse_recovering() works on tokens, so we have to lex first. (and this code has no
+// lexing errors, so that the parse errors are easier to see.)
+fn parse_recovering(source: &str) {
+	println!("Input: {}", source);
+
+	let tokens = lexing_toy::lex(source).expect("no lexing errors in these examples");
+	let (ast, errors) = Parser::parse_recovering(&tokens);
+
+	// Display prints the tree as code, which is easier to compare to the input.
+	print!("AST:");
+
+	for exp in ast {
+		print!(" {}", exp);
+	}
+
+	println!();
+
+	for e in errors {
+		println!("{} (at {})", e, e.span);
+	}
+
+	println!();
+}
+
+// &[Token] is to Vec<Token> as &str is to String.
+// &[Token] is a slice type, meaning this function can accept any type which can be
+// sliced (including Vecs and arrays).
+fn show_tokens(tokens: &[Token]) {
+	print!("Tokens: ");
 
 	for t in tokens {
-		print!("{} ", t);
+		print!("{} ", t.kind);
 	}
 
 	println!();
-}
\ No newline at end of file
+}
@@ -0,0 +1,31 @@
+// The lexer knows about floats, but the parser doesn't (there's no Exp for them), so a float is a
+// parse error, and not something that the parser quietly turns into an int or drops.
+
+use parsing_lisp::*;
+
+#[test]
+fn a_float_is_a_parse_error() {
+	match Parser::parse_str("(add 1 2.5)") {
+		Err(Error::Parse(e)) => {
+			assert_eq!(e.found, TokenKind::FloatLit(2.5));
+			assert_eq!((e.span.line, e.span.col), (1, 8));
+			assert_eq!(e.to_string(), "expected an expression, '.', or ')' to close '(' opened at 1:1, found '2.5'");
+		}
+
+		other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+	}
+
+	// outside of parens, too.
+	assert!(matches!(Parser::parse_str("1.5"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn a_float_becomes_an_error_when_recovering() {
+	let tokens = lexing_toy::lex("(f 1.5 . x)").expect("no lexing errors in these tests");
+	let (ast, errors) = Parser::parse_recovering(&tokens);
+
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].found, TokenKind::FloatLit(1.5));
+	assert_eq!(ast.len(), 1);
+	assert_eq!(ast[0].to_string(), "(f <error> . x)");
+}
@@ -0,0 +1,45 @@
+// When recovering, every '(' that's still open at the end of the input should get its own error,
+// even though they're all found at the same token (the Eof).
+
+use parsing_lisp::*;
+
+fn parse_recovering(source: &str) -> Vec<ParseError> {
+	let tokens = lexing_toy::lex(source).expect("no lexing errors in these tests");
+	let (_, errors) = Parser::parse_recovering(&tokens);
+	return errors;
+}
+
+// Where the '('s are that the errors at the end of the input say are missing their ')'s, as
+// (line, col), from the inside out.
+fn unclosed(errors: &[ParseError]) -> Vec<(usize, usize)> {
+	let mut ret = Vec::new();
+
+	for e in errors {
+		if let (TokenKind::Eof, [Expected::RParen(open)]) = (&e.found, &e.expected[..]) {
+			ret.push((open.line, open.col));
+		}
+	}
+
+	return ret;
+}
+
+#[test]
+fn each_unclosed_paren_is_reported() {
+	let errors = parse_recovering("((");
+	assert_eq!(errors.len(), 2);
+	assert_eq!(unclosed(&errors), vec![(1, 2), (1, 1)]);
+}
+
+#[test]
+fn unclosed_parens_after_other_errors() {
+	let errors = parse_recovering("(() (f 1.5 . x y");
+	assert_eq!(unclosed(&errors), vec![(1, 5), (1, 1)]);
+}
+
+#[test]
+fn one_missing_paren_is_only_reported_once() {
+	// parse_paren_exp() and expect_rparen() both notice this one.
+	let errors = parse_recovering("(a b");
+	assert_eq!(errors.len(), 1);
+	assert_eq!(unclosed(&errors), vec![(1, 1)]);
+}
@@ -1,5 +1,8 @@
 
 use std::fmt::{ Display, Formatter, Result as FmtResult };
+use std::ops::Range;
+
+use diagnostics::Diagnostic;
 
 // this line says that the "ast" module *exists*...
 mod ast;
@@ -118,13 +121,69 @@ impl TokenKind {
 	}
 }
 
+// Since this example has no lexer, this does the opposite: it writes the tokens out as source
+// code, with spaces between them, and gives the byte range of each token in it. That way, parse
+// errors have some source code to point at.
+pub fn tokens_to_source(tokens: &[TokenKind]) -> (String, Vec<Range<usize>>) {
+	let mut source = String::new();
+	let mut ranges = vec![];
+
+	for t in tokens {
+		if !source.is_empty() {
+			source.push(' ');
+		}
+
+		let start = source.len();
+		source += &t.to_string();
+		ranges.push(start .. source.len());
+	}
+
+	return (source, ranges);
+}
+
+// ------------------------------------------------------------------------------------------------
+// ParseError
+// ------------------------------------------------------------------------------------------------
+
+// The message is still just a String, to keep the code small, but the error also says *which
+// token* it was found at, so it can be shown with the diagnostics crate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+	pub index:   usize, // the index of the token where the error is. tokens.len() means the end.
+	pub message: String,
+}
+
+impl ParseError {
+	// Makes a Diagnostic which points at the token. ranges are the byte ranges of the tokens in
+	// the source, like tokens_to_source() gives.
+	pub fn to_diagnostic(&self, ranges: &[Range<usize>]) -> Diagnostic {
+		let range = match ranges.get(self.index) {
+			Some(range) => range.clone(),
+
+			// past the last token, so point just after it.
+			None => {
+				let end = ranges.last().map(|r| r.end).unwrap_or(0);
+				end .. end
+			}
+		};
+
+		return Diagnostic::error(&self.message, range);
+	}
+}
+
+impl Display for ParseError {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
 // ------------------------------------------------------------------------------------------------
 // The bottom-up expression parser
 // ------------------------------------------------------------------------------------------------
 
-// I'm keeping the code smaller by having the parser give a String for errors;
-// this is bad design, don't follow my lead here lolol
-type ParseResult = Result<Box<AstNode>, String>;
+type ParseResult = Result<Box<AstNode>, ParseError>;
 
 pub fn parse_exp(tokens: &[TokenKind]) -> ParseResult {
 	let mut p = Parser::new(tokens);
@@ -241,9 +300,9 @@ impl<'t> Parser<'t> {
 				return Ok(ret);
 			}
 
-			t => return Err(format!(
+			t => return Err(self.error(&format!(
 				"expected an identifier, number, or parenthesized expression, not '{}'", t
-			))
+			)))
 		}
 	}
 
@@ -272,17 +331,22 @@ impl<'t> Parser<'t> {
 		return Ok(lhs);
 	}
 
-	fn expect_rparen(&mut self) -> Result<(), String> {
+	fn expect_rparen(&mut self) -> Result<(), ParseError> {
 		match self.cur() {
 			TokenKind::RParen => { self.next(); return Ok(()); }
-			_ => return Err("expected a right parenthesis".into()),
+			_ => return Err(self.error("expected a right parenthesis")),
 		}
 	}
 
-	fn expect_eof(&mut self) -> Result<(), String> {
+	fn expect_eof(&mut self) -> Result<(), ParseError> {
 		match self.cur() {
 			TokenKind::Eof => return Ok(()),
-			_ => return Err("expected eof (there's extra stuff after the expression)".into()),
+			_ => return Err(self.error("expected eof (there's extra stuff after the expression)")),
 		}
 	}
+
+	// an error at the current token.
+	fn error(&self, message: &str) -> ParseError {
+		return ParseError { index: self.pos, message: message.into() };
+	}
 }
\ No newline at end of file
@@ -53,22 +53,14 @@ fn num(val: i32) -> TokenKind {
 }
 
 fn parse_it(tokens: &[TokenKind]) {
-	show_tokens(&tokens);
+	// there's no lexer, so the "source code" is just the tokens written out.
+	let (source, ranges) = tokens_to_source(tokens);
+	println!("Input tokens: {}", source);
 
 	match parse_exp(&tokens) {
 		Ok(ast)  => println!("AST: {}", ast),
-		Err(err) => println!("parse error: {}", err),
+		Err(err) => print!("{}", err.to_diagnostic(&ranges).render("<tokens>", &source)),
 	}
 
 	println!();
 }
-
-fn show_tokens(tokens: &[TokenKind]) {
-	print!("Input tokens: ");
-
-	for t in tokens {
-		print!("{} ", t);
-	}
-
-	println!();
-}
\ No newline at end of file
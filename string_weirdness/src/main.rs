@@ -0,0 +1,253 @@
+// The Rust version of StringWeirdness.java. Give it some strings, and it tells you everything
+// about them: how long they are (which has at least four different answers!), what codepoints
+// they're made of, and what they look like after each kind of Unicode normalization.
+//
+//     cargo run                                    # the strings from StringWeirdness.java, and more
+//     cargo run -- "tést" "te\u{301}st"            # your own strings
+//
+// Typing a combining accent in a terminal is hard, so the strings can use escapes: \u{301} is the
+// codepoint U+0301, and \\ is a backslash.
+//
+// If you give it more than one string, it also compares every pair of them, to see which ones are
+// "the same" after normalizing.
+
+use unicode_general_category::get_general_category;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// used when no strings are given on the command line.
+const DEFAULT_STRINGS: &[&str] = &[
+	// a precomposed 'é' (U+00E9), like s in StringWeirdness.java.
+	"t\u{E9}st",
+	// an 'e' followed by a combining accent (U+0301), like t in StringWeirdness.java.
+	"te\u{301}st",
+	// "fullwidth" letters (from East Asian typesetting). only the K normalizations say this is
+	// the same as the first two.
+	"\u{FF54}\u{E9}\u{FF53}\u{FF54}",
+	// an emoji, which Java needs two chars for.
+	"\u{1F600}",
+	// a family: three emoji glued together with "zero width joiners." one grapheme, but five
+	// codepoints!
+	"\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+];
+
+fn main() {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+	let strings = if args.is_empty() {
+		DEFAULT_STRINGS.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+	} else {
+		match args.iter().map(|a| unescape(a)).collect::<Result<Vec<_>, _>>() {
+			Ok(strings) => strings,
+			Err(e)      => {
+				eprintln!("error: {}", e);
+				std::process::exit(1);
+			}
+		}
+	};
+
+	for s in &strings {
+		inspect(s);
+		println!();
+	}
+
+	// compare every pair (but each pair only once, and not a string with itself).
+	for (i, a) in strings.iter().enumerate() {
+		for b in &strings[i + 1 ..] {
+			compare(a, b);
+			println!();
+		}
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Inspecting one string
+// ------------------------------------------------------------------------------------------------
+
+fn inspect(s: &str) {
+	println!("{}", quote(s));
+
+	// "how long is this string?" has a different answer depending on what you're counting.
+	//
+	// - bytes: Rust's String is UTF-8, where a codepoint takes 1 to 4 bytes. this is what
+	//   s.len() gives you.
+	// - chars: a Rust char is one codepoint (any of them, even emoji).
+	// - UTF-16 units: Java's String is UTF-16, where a codepoint takes one or two 16-bit chars.
+	//   this is what Java's length() gives you, and why "😀".length() is 2.
+	// - graphemes: what a *person* would call "characters." one grapheme can be several
+	//   codepoints, like an 'e' and the accent on top of it.
+	println!("    bytes (UTF-8):       {}", s.len());
+	println!("    chars (codepoints):  {}", s.chars().count());
+	println!("    UTF-16 units (Java): {}", s.encode_utf16().count());
+	println!("    graphemes:           {}", s.graphemes(true).count());
+
+	println!("    codepoints:");
+
+	for c in s.chars() {
+		print_codepoint(c);
+	}
+
+	// the normalization forms. the "C" ones ("composed") combine an 'e' and an accent into one
+	// 'é' codepoint; the "D" ones ("decomposed") split them apart. the "K" ones
+	// ("compatibility") also replace characters that are just different-looking versions of
+	// other characters, like 'ｔ' (fullwidth t) and 'ﬁ' (the "fi" ligature), with the plain ones.
+	println!("    normalized:");
+
+	for (name, normalized) in normalizations(s) {
+		let note = if normalized == s { " (no change)" } else { "" };
+		let len  = plural(normalized.chars().count(), "char");
+		println!("        {:4}  {} ({}){}", name, quote(&normalized), len, note);
+	}
+}
+
+fn print_codepoint(c: char) {
+	// unicode_names2 knows the official name of (almost) every codepoint. control characters
+	// like '\n' don't have names, just a category.
+	let name = match unicode_names2::name(c) {
+		Some(name) => name.to_string(),
+		None       => "<no name>".to_string(),
+	};
+
+	let category = get_general_category(c);
+
+	// {:3} would pad by counting chars, but some characters (like emoji and 'ｔ') take up two
+	// columns in the terminal, and combining marks take up none. so we pad it ourselves.
+	let shown   = show_char(c);
+	let padding = " ".repeat(3usize.saturating_sub(shown.width()));
+
+	println!("        {:8} {}{} {:2}  {} ({:?})",
+		format!("U+{:04X}", c as u32), shown, padding, category.abbreviation(), name, category);
+}
+
+// Some characters can't be shown on their own. control characters would mess up the output, and
+// "format" characters (like the zero width joiner) are invisible, so those are shown as escapes.
+// a combining mark needs something to combine with; Unicode's trick for showing one by itself is
+// to put it on a dotted circle (U+25CC), like "◌́".
+fn show_char(c: char) -> String {
+	match get_general_category(c).abbreviation() {
+		"Cc"               => return c.escape_default().to_string(),
+		"Cf"               => return c.escape_unicode().to_string(),
+		"Mn" | "Mc" | "Me" => return format!("\u{25CC}{}", c),
+		_                  => return c.to_string(),
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Comparing two strings
+// ------------------------------------------------------------------------------------------------
+
+fn compare(a: &str, b: &str) {
+	let results = normalizations(a).into_iter().zip(normalizations(b))
+		.map(|((name, a), (_, b))| (name, a == b))
+		.collect::<Vec<_>>();
+
+	print!("{} vs. {}", quote(a), quote(b));
+
+	// most pairs of strings are just different, and there's no point in saying so five times.
+	if a != b && results.iter().all(|&(_, same)| !same) {
+		println!(": different, even after normalizing");
+		return;
+	}
+
+	println!();
+	println!("    as written: {}", same_or_different(a == b));
+
+	for (name, same) in results {
+		println!("    {:11} {}", format!("{}:", name), same_or_different(same));
+	}
+}
+
+fn plural(n: usize, word: &str) -> String {
+	if n == 1 {
+		return format!("{} {}", n, word);
+	} else {
+		return format!("{} {}s", n, word);
+	}
+}
+
+fn same_or_different(same: bool) -> &'static str {
+	if same {
+		return "equal";
+	} else {
+		return "different";
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Helpers
+// ------------------------------------------------------------------------------------------------
+
+// All four normalization forms of a string, with their names.
+fn normalizations(s: &str) -> Vec<(&'static str, String)> {
+	return vec![
+		("NFC",  s.nfc().collect()),
+		("NFD",  s.nfd().collect()),
+		("NFKC", s.nfkc().collect()),
+		("NFKD", s.nfkd().collect()),
+	];
+}
+
+// Puts a string in quotes for printing. Control characters are escaped, but everything else is
+// left alone so you can see what it looks like.
+fn quote(s: &str) -> String {
+	let inside = s.chars().map(|c| {
+		if c.is_control() || c == '"' || c == '\\' {
+			c.escape_default().to_string()
+		} else {
+			c.to_string()
+		}
+	}).collect::<String>();
+
+	return format!("\"{}\"", inside);
+}
+
+// Turns the escapes in a command-line argument into the characters they stand for: \u{XXXX} is
+// the codepoint with that hex number, and \\ is one backslash.
+fn unescape(arg: &str) -> Result<String, String> {
+	let mut ret   = String::new();
+	let mut chars = arg.chars();
+
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			ret.push(c);
+			continue;
+		}
+
+		match chars.next() {
+			Some('\\') => ret.push('\\'),
+			Some('u')  => {
+				if chars.next() != Some('{') {
+					return Err(format!("in '{}': expected '{{' after \\u", arg));
+				}
+
+				// take_while() would happily stop at the end of the string, so we look for the
+				// '}' ourselves to make sure it's really there.
+				let mut hex    = String::new();
+				let mut closed = false;
+
+				for c in chars.by_ref() {
+					if c == '}' {
+						closed = true;
+						break;
+					}
+
+					hex.push(c);
+				}
+
+				if !closed {
+					return Err(format!("in '{}': expected '}}' at the end of \\u{{{}", arg, hex));
+				}
+
+				match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+					Some(c) => ret.push(c),
+					None    => return Err(format!("in '{}': '{}' is not a valid codepoint", arg, hex)),
+				}
+			}
+
+			_ => return Err(format!("in '{}': only \\u{{...}} and \\\\ escapes are allowed", arg)),
+		}
+	}
+
+	return Ok(ret);
+}
@@ -0,0 +1,131 @@
+// This file has the Symbol and Interner types. "Interning" a string means storing exactly one copy
+// of it in a table, and then referring to it by a small number (a Symbol) instead. Identifiers
+// show up over and over in a program, so this saves a lot of allocations. And since two Symbols
+// are equal if and only if their strings are equal, comparing names becomes comparing two
+// numbers, which is O(1), instead of comparing two strings character by character!
+
+use std::collections::HashMap;
+use std::fmt::{ Debug, Display, Formatter, Result as FmtResult };
+use std::sync::{ LazyLock, Mutex };
+
+use serde::{ Serialize, Serializer };
+
+// ------------------------------------------------------------------------------------------------
+// Symbol
+// ------------------------------------------------------------------------------------------------
+
+// A Symbol is just an index into the interner's table of strings. It's Copy, so you can pass it
+// around as easily as an integer.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+	// Interns s in the global interner (see below) and gives you its Symbol.
+	pub fn intern(s: &str) -> Self {
+		return global().intern(s);
+	}
+
+	// Gets the string for this Symbol from the global interner.
+	pub fn as_str(&self) -> &'static str {
+		return global().resolve(*self);
+	}
+
+	// The number inside the Symbol.
+	pub fn index(&self) -> u32 {
+		return self.0;
+	}
+}
+
+// Printing a Symbol prints its string, which is much more useful than printing its number.
+impl Display for Symbol {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+impl Debug for Symbol {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "Symbol({:?})", self.as_str())
+	}
+}
+
+// Same for JSON: a Symbol is written as its string, since the number means nothing outside this
+// program.
+impl Serialize for Symbol {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		return serializer.serialize_str(self.as_str());
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// Interner
+// ------------------------------------------------------------------------------------------------
+
+pub struct Interner {
+	// string -> Symbol, for interning.
+	map: HashMap<&'static str, Symbol>,
+
+	// Symbol -> string, for resolving. Symbol(i) is strings[i].
+	strings: Vec<&'static str>,
+}
+
+impl Interner {
+	pub fn new() -> Self {
+		return Interner { map: HashMap::new(), strings: vec![] };
+	}
+
+	// Gives the Symbol for s. If s has been interned before, you get the same Symbol as last
+	// time; otherwise, s is copied into the table and gets a new Symbol.
+	pub fn intern(&mut self, s: &str) -> Symbol {
+		if let Some(&sym) = self.map.get(s) {
+			return sym;
+		}
+
+		// Box::leak gives us a &'static str, a string that lives until the program exits. That
+		// means interned strings are never freed! That sounds bad, but it's fine for a compiler:
+		// there are only so many different identifiers in a program, and a compiler doesn't
+		// run for very long. In exchange, resolve() can give out &str without any lifetime
+		// headaches.
+		let s: &'static str = Box::leak(s.to_string().into_boxed_str());
+		let sym = Symbol(self.strings.len() as u32);
+
+		self.strings.push(s);
+		self.map.insert(s, sym);
+		return sym;
+	}
+
+	// Symbol -> string. Panics if the Symbol came from a different Interner.
+	pub fn resolve(&self, sym: Symbol) -> &'static str {
+		return self.strings[sym.0 as usize];
+	}
+
+	// How many different strings have been interned.
+	pub fn len(&self) -> usize {
+		return self.strings.len();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.strings.is_empty();
+	}
+}
+
+impl Default for Interner {
+	fn default() -> Self {
+		return Interner::new();
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// The global interner
+// ------------------------------------------------------------------------------------------------
+
+// The lexer uses one global interner, so that every Symbol means the same thing everywhere and
+// you don't have to pass an Interner around to every function that wants to print a name.
+// Like NODE_ID in visit_names, it's a global, so it has to be safe to use from multiple threads.
+// A Mutex makes sure only one thread can use it at a time, and LazyLock creates it the first time
+// it's used (since HashMap::new() can't be called when the program is compiled).
+static INTERNER: LazyLock<Mutex<Interner>> = LazyLock::new(|| Mutex::new(Interner::new()));
+
+fn global() -> std::sync::MutexGuard<'static, Interner> {
+	return INTERNER.lock().expect("some thread panicked while using the interner");
+}
@@ -0,0 +1,118 @@
+// The token types, shared by the lexer (lexing_toy) and the parser (parsing_lisp). The lexer
+// makes Tokens and the parser reads them, so they both need to agree on what a Token *is*. Putting
+// the types in their own crate lets both of them use it, without one depending on the other just
+// for the types.
+//
+// Tokens need a few other types too: a Span says where a token is, a SourceMap turns Spans into
+// line and column numbers, and a Symbol is an interned identifier.
+
+use std::fmt::{ Display, Formatter, Result as FmtResult };
+
+use serde::Serialize;
+
+mod intern;
+mod span;
+
+pub use crate::intern::*;
+pub use crate::span::*;
+
+// ------------------------------------------------------------------------------------------------
+// Token type
+// ------------------------------------------------------------------------------------------------
+
+// The grammar for these tokens is at the top of lexing_toy's lib.rs.
+//
+// (this can't derive Eq, because f64 doesn't implement Eq. NaN != NaN, so floats can't promise
+// that everything is equal to itself!)
+//
+// the serde attribute says how to write it as JSON: {"kind": "IntLit", "value": 5}. tokens with
+// nothing inside, like LParen, only get the "kind".
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum TokenKind {
+	// Eof represents the end of the input. There will be one Eof token at the
+	// end of the lexer output.
+	Eof,
+	LParen,
+	RParen,
+	Quote, // the ' in 'x, which is short for (quote x).
+	Dot,   // the . in a dotted pair, like (1 . 2).
+	Id(Symbol), // identifiers are interned. use .as_str() on the Symbol to get the name.
+	IntLit(i64),
+	FloatLit(f64),
+	StrLit(String), // the escape sequences have already been turned into the real characters.
+
+	// Comment is only produced if you ask for it (see Lexer::with_comments in lexing_toy).
+	// Normally comments are skipped just like whitespace. The string is the whole comment,
+	// including the ';' or the '#|' and '|#'.
+	Comment(String),
+
+	// these three are only produced in indentation mode (see lexing_toy's indent.rs). Newline
+	// ends a line, and Indent and Dedent are like '(' and ')' for lines that are indented more
+	// than the lines around them.
+	Newline,
+	Indent,
+	Dedent,
+
+	// and these are only produced in interpolation mode (see lexing_toy's interp.rs). in that
+	// mode, strings are lexed as StrStart, then StrPart (some text) and InterpStart ... InterpEnd
+	// (the code in a "${...}") in any order, then StrEnd.
+	StrStart,
+	StrPart(String),
+	InterpStart,
+	InterpEnd,
+	StrEnd,
+
+	// Error is only produced by a recovering lexer (see Lexer::new_recovering in lexing_toy). It
+	// stands in for a run of characters that couldn't be lexed, so that the rest of the input can
+	// still be turned into tokens.
+	Error,
+}
+
+// Display shows a token the way it looks in the source code. (the tokens that don't have any
+// text, like Indent, are shown in <angle brackets> so you can still see them.)
+impl Display for TokenKind {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		use TokenKind::*;
+
+		match self {
+			Eof         => write!(f, ""),
+			LParen      => write!(f, "("),
+			RParen      => write!(f, ")"),
+			Quote       => write!(f, "'"),
+			Dot         => write!(f, "."),
+			Id(id)      => write!(f, "{}", id),
+			IntLit(i)   => write!(f, "{}", i),
+			FloatLit(x) => write!(f, "{:?}", x), // {:?} always shows the '.', so 1.0 isn't 1.
+			StrLit(s)   => write!(f, "{:?}", s),
+			Comment(c)  => write!(f, "{}", c),
+			Newline     => write!(f, "<newline>"),
+			Indent      => write!(f, "<indent>"),
+			Dedent      => write!(f, "<dedent>"),
+			StrStart    => write!(f, "\""),
+			StrPart(s)  => write!(f, "{}", s.escape_debug()),
+			InterpStart => write!(f, "${{"),
+			InterpEnd   => write!(f, "}}"),
+			StrEnd      => write!(f, "\""),
+			Error       => write!(f, "<error>"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Token {
+	pub span: Span, // where in the source code this token is.
+
+	// "flatten" puts the kind and value right in the token's JSON object, instead of nesting them.
+	#[serde(flatten)]
+	pub kind: TokenKind,
+}
+
+impl Token {
+	// Self is a "magical type" that can be used in impl blocks, and refers to the type
+	// that the impl is attached to. Here it means "Token". It doesn't save much typing
+	// here, but if it were a big generic type with arguments, it would!
+	pub fn new(span: Span, kind: TokenKind) -> Self {
+		return Token { span, kind };
+	}
+}
@@ -0,0 +1,270 @@
+// This file has the Span type, which says *where* in the source code a token or error is, and the
+// SourceMap type, which converts between the different ways of describing a position.
+
+use std::fmt::{ Display, Formatter, Result as FmtResult };
+use std::ops::Range;
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+// ------------------------------------------------------------------------------------------------
+// Span
+// ------------------------------------------------------------------------------------------------
+
+// A Span is a range of the source code. start and end are codepoint indices, and end is
+// *exclusive*, like a Rust range (start..end). line and col are where start is, and they
+// both start counting at 1, because that's how text editors show them. col counts graphemes,
+// not codepoints (see SourceMap below).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize)]
+pub struct Span {
+	pub start: usize,
+	pub end:   usize,
+	pub line:  usize,
+	pub col:   usize,
+}
+
+impl Span {
+	pub fn new(start: usize, end: usize, line: usize, col: usize) -> Self {
+		return Span { start, end, line, col };
+	}
+
+	// How many codepoints long this span is.
+	pub fn len(&self) -> usize {
+		return self.end - self.start;
+	}
+
+	// Only the Eof token has an empty span.
+	pub fn is_empty(&self) -> bool {
+		return self.start == self.end;
+	}
+}
+
+// Spans print as "line:col", which is what you usually want to show a user.
+impl Display for Span {
+	fn fmt(&self, f: &mut Formatter) -> FmtResult {
+		write!(f, "{}:{}", self.line, self.col)
+	}
+}
+
+// ------------------------------------------------------------------------------------------------
+// SourceMap
+// ------------------------------------------------------------------------------------------------
+
+// There are three common ways to talk about a position in some source code:
+//
+// - byte offset: how many bytes from the start of the UTF-8 string. This is what you need to
+//   slice a &str, like &source[a .. b].
+// - codepoint index: how many chars from the start. This is what our lexer uses, since it
+//   works on a Vec<char>.
+// - line and column: what a human wants to see.
+//
+// These are only the same if the source is all ASCII and all on one line! A SourceMap is built
+// once for some source code, and then it can convert between all three quickly.
+//
+// The column counts *graphemes* (or "grapheme clusters"), which are what a person would call one
+// character. "é" can be written as one codepoint (U+00E9), or as an 'e' followed by a combining
+// accent (U+0301), which is two codepoints. Either way it looks like one character, so it should
+// be one column. (StringWeirdness.java in the root of this repo shows why this is confusing!)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+	// byte_offsets[i] is the byte offset of codepoint i. there is one extra entry at the end
+	// which is the length of the source in bytes, so that the end of the source has an offset.
+	byte_offsets: Vec<usize>,
+
+	// line_starts[i] is the codepoint index where line i + 1 starts. line 1 always starts at 0.
+	line_starts: Vec<usize>,
+
+	// cols[i] is the column of codepoint i. all the codepoints in one grapheme have the same
+	// column. like byte_offsets, there's an extra one at the end for the end of the source.
+	cols: Vec<usize>,
+}
+
+impl SourceMap {
+	pub fn new(source: &str) -> Self {
+		let mut byte_offsets = vec![];
+		let mut line_starts  = vec![0];
+
+		// char_indices() gives us each char along with its byte offset.
+		for (i, (offset, c)) in source.char_indices().enumerate() {
+			byte_offsets.push(offset);
+
+			// the line after a newline starts at the next codepoint.
+			if c == '\n' {
+				line_starts.push(i + 1);
+			}
+		}
+
+		byte_offsets.push(source.len());
+
+		let mut cols = vec![];
+		line_cols(source, &mut cols);
+
+		return SourceMap { byte_offsets, line_starts, cols };
+	}
+
+	// Updates the map after an edit that replaced the codepoints in range with text. new_source
+	// is the whole source *after* the edit. Unlike new(), this doesn't go over the whole source
+	// again: only the lines the edit touched are measured again, and everything after them just
+	// moves over by however much longer or shorter the source got.
+	pub fn edit(&mut self, new_source: &str, range: Range<usize>, text: &str) {
+		let delta      = text.chars().count() as isize - range.len() as isize;
+		let byte_start = self.byte_offsets[range.start];
+		let byte_delta = text.len() as isize - (self.byte_offsets[range.end] - byte_start) as isize;
+
+		// the lines that the edit starts and ends on, and where the end of the last one was.
+		let first_line = self.line_starts.partition_point(|&s| s <= range.start) - 1;
+		let last_line  = self.line_starts.partition_point(|&s| s <= range.end) - 1;
+		let old_end    = match self.line_starts.get(last_line + 1) {
+			Some(&next) => next - 1, // the '\n' at the end of the line.
+			None        => self.num_chars(),
+		};
+
+		// byte offsets: the ones after the edit move over, and the new text gets new ones.
+		for offset in &mut self.byte_offsets[range.end ..] {
+			*offset = (*offset as isize + byte_delta) as usize;
+		}
+
+		let new_offsets = text.char_indices().map(|(offset, _)| byte_start + offset);
+		self.byte_offsets.splice(range.clone(), new_offsets);
+
+		// line starts: the lines after the edit move over. the newlines that were in the range
+		// are gone, and the newlines in the new text start new lines.
+		for start in &mut self.line_starts[last_line + 1 ..] {
+			*start = (*start as isize + delta) as usize;
+		}
+
+		let new_starts = text.chars().enumerate()
+			.filter(|&(_, c)| c == '\n')
+			.map(|(i, _)| range.start + i + 1)
+			.collect::<Vec<_>>();
+		let new_lines = new_starts.len();
+		self.line_starts.splice(first_line + 1 ..= last_line, new_starts);
+
+		// columns: measure the lines that the edit touched again. the columns after those lines
+		// don't change at all, since each line's columns start over at 1.
+		let start     = self.line_starts[first_line];
+		let new_end   = match self.line_starts.get(first_line + new_lines + 1) {
+			Some(&next) => next - 1,
+			None        => self.num_chars(),
+		};
+
+		let lines = &new_source[self.byte_offsets[start] .. self.byte_offsets[new_end]];
+		let mut new_cols = vec![];
+		line_cols(lines, &mut new_cols);
+		self.cols.splice(start ..= old_end, new_cols);
+	}
+
+	// How many codepoints are in the source.
+	pub fn num_chars(&self) -> usize {
+		return self.byte_offsets.len() - 1;
+	}
+
+	// How many lines are in the source. Even an empty string has 1 line.
+	pub fn num_lines(&self) -> usize {
+		return self.line_starts.len();
+	}
+
+	// Codepoint index -> byte offset. Indexes past the end are clamped to the end.
+	pub fn char_to_byte(&self, index: usize) -> usize {
+		return self.byte_offsets[index.min(self.num_chars())];
+	}
+
+	// Byte offset -> codepoint index. If the offset is in the middle of a multi-byte character,
+	// you get the index of that character.
+	pub fn byte_to_char(&self, offset: usize) -> usize {
+		// binary_search returns Ok(i) if it found it exactly, or Err(i) where i is where it
+		// *would* go. in the second case, the character that contains the offset is at i - 1.
+		match self.byte_offsets.binary_search(&offset) {
+			Ok(i)  => return i,
+			Err(i) => return i - 1,
+		}
+	}
+
+	// Codepoint index -> (line, col), both starting at 1. If the codepoint is in the middle of a
+	// grapheme (like a combining accent), you get the column of that whole grapheme.
+	pub fn line_col(&self, index: usize) -> (usize, usize) {
+		let index = index.min(self.num_chars());
+
+		// same binary search trick as above: find the last line that starts at or before index.
+		let line = match self.line_starts.binary_search(&index) {
+			Ok(i)  => i,
+			Err(i) => i - 1,
+		};
+
+		return (line + 1, self.cols[index]);
+	}
+
+	// (line, col) -> codepoint index of the first codepoint in that column. Returns None if that
+	// line doesn't exist, or if the column is past the end of that line.
+	pub fn index_of(&self, line: usize, col: usize) -> Option<usize> {
+		if line == 0 || col == 0 || line > self.num_lines() {
+			return None;
+		}
+
+		// the end of the line (where the '\n' or the end of the source is) is a valid position.
+		// the columns only go up along a line, so we can binary search for the first codepoint
+		// with this column.
+		let line_range = self.line_range(line);
+		let cols       = &self.cols[line_range.start ..= line_range.end];
+		let i          = cols.partition_point(|&c| c < col);
+
+		if i < cols.len() && cols[i] == col {
+			return Some(line_range.start + i);
+		} else {
+			return None;
+		}
+	}
+
+	// Byte offset -> (line, col).
+	pub fn byte_line_col(&self, offset: usize) -> (usize, usize) {
+		return self.line_col(self.byte_to_char(offset));
+	}
+
+	// The codepoint range of a line, not including its '\n'. Lines start at 1.
+	pub fn line_range(&self, line: usize) -> std::ops::Range<usize> {
+		let start = self.line_starts[line - 1];
+
+		let end = if line < self.num_lines() {
+			self.line_starts[line] - 1 // back up over the '\n'.
+		} else {
+			self.num_chars()
+		};
+
+		return start .. end;
+	}
+
+	// Makes a Span from a range of codepoint indices, filling in the line and column.
+	pub fn span(&self, start: usize, end: usize) -> Span {
+		let (line, col) = self.line_col(start);
+		return Span::new(start, end, line, col);
+	}
+
+	// The byte range of a span, for slicing the source string like &source[range].
+	pub fn byte_range(&self, span: Span) -> std::ops::Range<usize> {
+		return self.char_to_byte(span.start) .. self.char_to_byte(span.end);
+	}
+}
+
+// Pushes the column of each codepoint in source onto cols, plus one more for the end of each line.
+// graphemes() comes from the unicode-segmentation crate, and splits a string into graphemes. we
+// do it one line at a time, so that the columns start over on each line.
+fn line_cols(source: &str, cols: &mut Vec<usize>) {
+	for line in source.split('\n') {
+		// in ASCII, every character is its own grapheme, so we can skip the hard part.
+		if line.is_ascii() {
+			cols.extend(1 ..= line.len() + 1);
+			continue;
+		}
+
+		let mut num_graphemes = 0;
+
+		for (col, g) in line.graphemes(true).enumerate() {
+			cols.extend(g.chars().map(|_| col + 1));
+			num_graphemes += 1;
+		}
+
+		// the column of the '\n' at the end of the line (or the end of the source, after the
+		// last line).
+		cols.push(num_graphemes + 1);
+	}
+}
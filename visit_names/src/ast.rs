@@ -4,6 +4,8 @@
 
 use std::sync::atomic::{ AtomicUsize, Ordering };
 
+use tokens::Symbol;
+
 // ------------------------------------------------------------------------------------
 // Node IDs
 // ------------------------------------------------------------------------------------
@@ -24,15 +26,19 @@ pub fn new_node_id() -> usize {
 
 // Idents are used a lot in the namechecking. They have a node id field, so that they
 // can be referred to by the symbol tables, decl map, and use map.
+//
+// The name is an interned Symbol from the tokens crate, not a String. Namechecking compares names
+// *constantly* (every lookup in a symbol table does it), and comparing two Symbols is just
+// comparing two numbers, no matter how long the names are.
 #[derive(Debug)]
 pub struct Ident {
 	pub id:   usize,
-	pub name: String,
+	pub name: Symbol,
 }
 
 impl Ident {
 	pub fn new(name: &str) -> Self {
-		return Self { id: new_node_id(), name: name.into() };
+		return Self { id: new_node_id(), name: Symbol::intern(name) };
 	}
 }
 
@@ -40,7 +46,10 @@ impl Ident {
 // Program
 // ------------------------------------------------------------------------------------
 
+// The Box<Decl>s (and Box<Stmt>s and Box<Exp>s below) inside Vecs are just like the AST in the
+// projects, so clippy's vec_box lint is turned off for them.
 #[derive(Debug)]
+#[allow(clippy::vec_box)]
 pub struct Program {
 	pub decls: Vec<Box<Decl>>,
 }
@@ -86,6 +95,7 @@ impl Decl {
 // ------------------------------------------------------------------------------------
 
 #[derive(Debug)]
+#[allow(clippy::vec_box)]
 pub enum StmtKind {
 	Block  (Vec<Box<Stmt>>),
 	Exp    (Box<Exp>),
@@ -95,6 +105,7 @@ pub enum StmtKind {
 
 #[derive(Debug)]
 pub struct Stmt {
+	#[allow(dead_code)] // nothing in this example uses statement IDs, but a real AST has them.
 	pub id: usize,
 	pub kind: StmtKind,
 }
@@ -110,8 +121,10 @@ impl Stmt {
 // ------------------------------------------------------------------------------------
 
 #[derive(Debug)]
+#[allow(clippy::vec_box)]
 pub enum ExpKind {
 	Id     (Ident),
+	#[allow(dead_code)] // ints aren't names, so the visitor never looks at the value.
 	IntLit (i64),
 	Call   { callee: Box<Exp>, args: Vec<Box<Exp>> },
 }
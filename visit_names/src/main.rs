@@ -63,6 +63,7 @@ fn vardecl(name: &str, init: Box<Exp>) -> Box<Decl> {
 	});
 }
 
+#[allow(clippy::vec_box)]
 fn funcdecl(name: &str, args: Vec<&str>, code: Vec<Box<Stmt>>) -> Box<Decl> {
 	return Decl::new_func(FuncDecl {
 		name: Ident::new(name),
@@ -86,6 +87,7 @@ fn letstmt(name: &str, init: Box<Exp>) -> Box<Stmt> {
 	}));
 }
 
+#[allow(clippy::vec_box)]
 fn callstmt(callee: &str, args: Vec<Box<Exp>>) -> Box<Stmt> {
 	return Stmt::new(StmtKind::Exp(Exp::new(ExpKind::Call {
 		callee: Exp::new(ExpKind::Id(Ident::new(callee))),
@@ -100,6 +102,7 @@ fn assignstmt(dst: &str, src: &str) -> Box<Stmt> {
 	});
 }
 
+#[allow(clippy::vec_box)]
 fn blockstmt(stmts: Vec<Box<Stmt>>) -> Box<Stmt> {
 	return Stmt::new(StmtKind::Block(stmts));
 }
\ No newline at end of file
@@ -25,11 +25,11 @@ impl ScopeVisitor {
 		for d in &prog.decls {
 			match d.as_ref() {
 				Decl::Var(vd) => {
-					println!("global variable decl: '{}' (node id: {})", vd.name.name.red(), vd.name.id);
+					println!("global variable decl: '{}' (node id: {})", vd.name.name.as_str().red(), vd.name.id);
 				}
 
 				Decl::Func(fd) => {
-					println!("function decl: '{}' (node id: {})", fd.name.name.red(), fd.name.id);
+					println!("function decl: '{}' (node id: {})", fd.name.name.as_str().red(), fd.name.id);
 				}
 			}
 		}
@@ -54,7 +54,7 @@ impl ScopeVisitor {
 	// When this is called, the variable's name has already been "recorded" so we only
 	// need to visit its initializer.
 	pub fn visit_global_var_decl(&self, vd: &VarDecl) {
-		println!("Visiting {}'s initializer...", vd.name.name.yellow());
+		println!("Visiting {}'s initializer...", vd.name.name.as_str().yellow());
 		self.visit_exp(&vd.init, 1);
 		println!();
 	}
@@ -63,11 +63,11 @@ impl ScopeVisitor {
 	pub fn visit_func_decl(&self, fd: &FuncDecl) {
 		// Each function has its own scope, and these BEGIN/END SCOPE messages indicate
 		// where we would create a new scope and where we would go back to the parent scope.
-		println!("BEGIN SCOPE for function {} (node id: {})", fd.name.name.yellow(), fd.name.id);
+		println!("BEGIN SCOPE for function {} (node id: {})", fd.name.name.as_str().yellow(), fd.name.id);
 
 		// Arguments are really local variables!
 		for arg in &fd.args {
-			println!("    argument decl: '{}' (node id: {})", arg.name.red(), arg.id);
+			println!("    argument decl: '{}' (node id: {})", arg.name.as_str().red(), arg.id);
 		}
 
 		// Then we visit the code inside this function.
@@ -80,14 +80,14 @@ impl ScopeVisitor {
 	// Method to visit an expression node. scope_depth is used to keep track of how many
 	// scopes we are nested in, so we can print out the right number of indentations before
 	// each line.
-	pub fn visit_exp(&self, exp: &Box<Exp>, scope_depth: usize) {
+	pub fn visit_exp(&self, exp: &Exp, scope_depth: usize) {
 		let indent = "    ".repeat(scope_depth);
 
 		match &exp.kind {
 			ExpKind::Id(ident) => {
 				// vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv
 				// this right here is where NAME RESOLUTION would occur!
-				println!("{}I see a use of '{}' at AST node {}!", indent, ident.name.green(), exp.id);
+				println!("{}I see a use of '{}' at AST node {}!", indent, ident.name.as_str().green(), exp.id);
 				// ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
 			}
 
@@ -107,7 +107,7 @@ impl ScopeVisitor {
 	}
 
 	// Method to visit a statement node. for scope_depth, see the explanation on visit_exp.
-	pub fn visit_stmt(&self, stmt: &Box<Stmt>, scope_depth: usize) {
+	pub fn visit_stmt(&self, stmt: &Stmt, scope_depth: usize) {
 		let indent = "    ".repeat(scope_depth);
 
 		match &stmt.kind {
@@ -140,7 +140,7 @@ impl ScopeVisitor {
 				// you can't do weird stuff like "let x = x;" and have the x on the right side of
 				// the = refer to the x on the left side.
 				println!("{}local variable decl: '{}' (node id: {})",
-					indent, vd.name.name.red(), vd.name.id);
+					indent, vd.name.name.as_str().red(), vd.name.id);
 				println!("{}visiting its initializer...", indent);
 
 				self.visit_exp(&vd.init, scope_depth + 1);